@@ -0,0 +1,85 @@
+use near_sdk::{env, require, AccountId};
+
+use crate::errors::ERR_CONTRACT_ACCOUNT;
+use crate::Contract;
+
+impl Contract {
+    /// Panics if `account_id` is the token contract itself, for roles and mint recipients.
+    /// Transfers to the contract account stay possible, see `recover_tokens`.
+    pub(crate) fn assert_valid_external_account(&self, account_id: &AccountId) {
+        require!(
+            *account_id != env::current_account_id(),
+            ERR_CONTRACT_ACCOUNT
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{current, owner, register_user, setup, user1};
+
+    fn as_owner(context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+    }
+
+    #[test]
+    fn test_valid_account_ids() {
+        let (contract, _) = setup();
+
+        for account_id in [
+            "ab",
+            "alice.near",
+            "a-b_c.testnet",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ] {
+            let account_id: AccountId = account_id.parse().unwrap();
+            contract.assert_valid_external_account(&account_id);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "The token contract account can't be used here")]
+    fn test_mint_to_contract_account() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, current());
+
+        as_owner(&mut context);
+        contract.add_minter(owner());
+        contract.mint(current(), 100.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The token contract account can't be used here")]
+    fn test_contract_account_as_owner() {
+        let (mut contract, mut context) = setup();
+
+        as_owner(&mut context);
+        contract.update_owner(current());
+    }
+
+    #[test]
+    #[should_panic(expected = "The token contract account can't be used here")]
+    fn test_contract_account_as_minter() {
+        let (mut contract, mut context) = setup();
+
+        as_owner(&mut context);
+        contract.set_minters(vec![user1(), current()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The token contract account can't be used here")]
+    fn test_contract_account_as_treasury() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, current());
+
+        as_owner(&mut context);
+        contract.set_treasury(current());
+    }
+}
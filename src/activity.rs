@@ -0,0 +1,336 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, NearToken, Promise};
+
+use crate::errors::{
+    ERR_NO_INACTIVITY_THRESHOLD, ERR_RECENTLY_ACTIVE, ERR_RECLAIM_POSITIVE_BALANCE,
+};
+use crate::events::emit_event;
+use crate::{Contract, ContractExt};
+
+/// Data of the `balance_zeroed` event emitted when a transfer empties the sender's balance.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct BalanceZeroed<'a> {
+    account_id: &'a AccountId,
+}
+
+#[near]
+impl Contract {
+    /// Enables or disables tracking of the last transfer time of each account. Disabled by
+    /// default because every tracked account takes contract storage.
+    #[payable]
+    pub fn set_activity_tracking_enabled(&mut self, enabled: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.activity_tracking_enabled = enabled;
+    }
+
+    pub fn is_activity_tracking_enabled(&self) -> bool {
+        self.activity_tracking_enabled
+    }
+
+    /// Returns the block timestamp of the last transfer `account_id` sent or received while
+    /// activity tracking was enabled.
+    pub fn last_active(&self, account_id: AccountId) -> Option<U64> {
+        self.last_active.get(&account_id).map(U64)
+    }
+
+    /// Returns the number of distinct accounts that sent or received a transfer during the
+    /// current epoch while activity tracking was enabled. The count restarts from zero with
+    /// the first transfer of each epoch, so enabling tracking mid-epoch only counts the
+    /// accounts active since then.
+    pub fn active_accounts_this_epoch(&self) -> u64 {
+        if self.active_accounts_epoch_height == env::epoch_height() {
+            self.active_accounts_count
+        } else {
+            0
+        }
+    }
+
+    /// Sets how long an account must stay inactive before `reclaim_inactive` can close it,
+    /// or disables reclaiming with `None`.
+    #[payable]
+    pub fn set_inactivity_threshold(&mut self, threshold_ns: Option<U64>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.inactivity_threshold_ns = threshold_ns.map(|threshold_ns| threshold_ns.0);
+    }
+
+    pub fn get_inactivity_threshold(&self) -> Option<U64> {
+        self.inactivity_threshold_ns.map(U64)
+    }
+
+    /// Unregisters `account_id` if it holds no tokens and its last tracked activity is older
+    /// than the inactivity threshold, and sends the NEAR its storage deposit covered to the
+    /// treasury. Accounts without tracked activity can't be reclaimed. Returns the NEAR sent.
+    #[payable]
+    pub fn reclaim_inactive(&mut self, account_id: AccountId) -> NearToken {
+        assert_one_yocto();
+        self.assert_owner();
+        let threshold_ns = self
+            .inactivity_threshold_ns
+            .unwrap_or_else(|| env::panic_str(ERR_NO_INACTIVITY_THRESHOLD));
+        let balance = self.token.accounts.get(&account_id).unwrap_or_else(|| {
+            env::panic_str(&format!("The account {} is not registered", account_id))
+        });
+        require!(balance == 0, ERR_RECLAIM_POSITIVE_BALANCE);
+        require!(
+            self.last_active
+                .get(&account_id)
+                .is_some_and(|last_active| last_active.saturating_add(threshold_ns)
+                    <= env::block_timestamp()),
+            ERR_RECENTLY_ACTIVE
+        );
+
+        let covered = self
+            .storage_covered
+            .remove(&account_id)
+            .unwrap_or(self.base_account_storage_usage);
+        self.token.accounts.remove(&account_id);
+        self.internal_clear_account_state(&account_id);
+        let reclaimed = env::storage_byte_cost().saturating_mul(covered.into());
+        log!(
+            "Reclaimed the storage of inactive account {}: {} sent to the treasury",
+            account_id,
+            reclaimed
+        );
+        Promise::new(self.treasury_id.clone()).transfer(reclaimed);
+        reclaimed
+    }
+}
+
+impl Contract {
+    /// Marks both sides of a transfer as active at the current block timestamp, if activity
+    /// tracking is enabled.
+    pub(crate) fn record_activity(&mut self, sender_id: &AccountId, receiver_id: &AccountId) {
+        if !self.activity_tracking_enabled {
+            return;
+        }
+        let now = env::block_timestamp();
+        self.last_active.insert(sender_id, &now);
+        self.last_active.insert(receiver_id, &now);
+        self.mark_active_this_epoch(sender_id);
+        self.mark_active_this_epoch(receiver_id);
+    }
+
+    /// Counts `account_id` among the active accounts of the current epoch, once.
+    fn mark_active_this_epoch(&mut self, account_id: &AccountId) {
+        let epoch_height = env::epoch_height();
+        if self.active_accounts_epoch_height != epoch_height {
+            self.active_accounts_epoch_height = epoch_height;
+            self.active_accounts_count = 0;
+        }
+        if self.last_active_epoch.insert(account_id, &epoch_height) != Some(epoch_height) {
+            self.active_accounts_count += 1;
+        }
+    }
+
+    /// Emits a `balance_zeroed` event if the transfer just made by `sender_id` emptied its
+    /// balance, so indexers can trigger their cleanup. The account stays registered.
+    pub(crate) fn check_balance_zeroed(&self, sender_id: &AccountId) {
+        if self.token.accounts.get(sender_id) == Some(0) {
+            emit_event(
+                "balance_zeroed",
+                BalanceZeroed {
+                    account_id: sender_id,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    fn transfer_at(contract: &mut Contract, context: &mut VMContextBuilder, timestamp: u64) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.ft_transfer(user1(), 10.into(), None);
+    }
+
+    fn transfer_in_epoch(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        epoch_height: u64,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(sender_id)
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .epoch_height(epoch_height)
+            .build());
+        contract.ft_transfer(receiver_id, 10.into(), None);
+    }
+
+    #[test]
+    fn test_active_accounts_this_epoch() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_activity_tracking_enabled(true);
+
+        transfer_in_epoch(&mut contract, &mut context, owner(), user1(), 1);
+        transfer_in_epoch(&mut contract, &mut context, owner(), user1(), 1);
+        assert_eq!(contract.active_accounts_this_epoch(), 2);
+        transfer_in_epoch(&mut contract, &mut context, user1(), user2(), 1);
+        assert_eq!(contract.active_accounts_this_epoch(), 3);
+
+        // a new epoch starts from zero
+        testing_env!(context.epoch_height(2).build());
+        assert_eq!(contract.active_accounts_this_epoch(), 0);
+        transfer_in_epoch(&mut contract, &mut context, user2(), user1(), 2);
+        assert_eq!(contract.active_accounts_this_epoch(), 2);
+    }
+
+    #[test]
+    fn test_active_accounts_not_counted_without_tracking() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        transfer_in_epoch(&mut contract, &mut context, owner(), user1(), 1);
+        assert_eq!(contract.active_accounts_this_epoch(), 0);
+    }
+
+    #[test]
+    fn test_balance_zeroed_event() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        transfer_at(&mut contract, &mut context, 0);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user2(), 4.into(), None);
+        assert!(!get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"balance_zeroed""#)));
+        assert_eq!(contract.nonzero_holder_count(), 3);
+
+        contract.ft_transfer(user2(), 6.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+        assert_eq!(contract.nonzero_holder_count(), 2);
+        assert!(get_logs().contains(&format!(
+            r#"EVENT_JSON:{{"standard":"publicai","version":"1.0.0","event":"balance_zeroed","data":[{{"account_id":"{}"}}]}}"#,
+            user1()
+        )));
+        // the emptied account stays registered
+        assert!(contract.token.accounts.contains_key(&user1()));
+    }
+
+    #[test]
+    fn test_last_active_updated_on_transfer() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_activity_tracking_enabled(true);
+
+        transfer_at(&mut contract, &mut context, 1_000);
+        assert_eq!(contract.last_active(owner()), Some(U64(1_000)));
+        assert_eq!(contract.last_active(user1()), Some(U64(1_000)));
+
+        transfer_at(&mut contract, &mut context, 2_000);
+        assert_eq!(contract.last_active(user1()), Some(U64(2_000)));
+        assert_eq!(contract.last_active(user2()), None);
+    }
+
+    /// Tracks activity, lets user1 (holding 10 tokens) be active at 1_000 and sets a 5_000
+    /// inactivity threshold.
+    fn setup_dormant() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_activity_tracking_enabled(true);
+        contract.set_inactivity_threshold(Some(5_000.into()));
+        transfer_at(&mut contract, &mut context, 1_000);
+        (contract, context)
+    }
+
+    fn reclaim_at(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        timestamp: u64,
+    ) -> NearToken {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.reclaim_inactive(user1())
+    }
+
+    fn empty_user1(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(1_000)
+            .build());
+        contract.ft_transfer(owner(), 10.into(), None);
+    }
+
+    #[test]
+    fn test_reclaim_inactive() {
+        let (mut contract, mut context) = setup_dormant();
+        empty_user1(&mut contract, &mut context);
+        let min = contract.storage_balance_bounds().min;
+
+        assert_eq!(reclaim_at(&mut contract, &mut context, 6_000), min);
+
+        assert!(!contract.token.accounts.contains_key(&user1()));
+        assert_eq!(contract.last_active(user1()), None);
+        assert_eq!(contract.storage_stats().num_accounts, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only accounts with a zero balance can be reclaimed")]
+    fn test_reclaim_inactive_with_balance() {
+        let (mut contract, mut context) = setup_dormant();
+
+        reclaim_at(&mut contract, &mut context, 6_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account was active within the inactivity threshold")]
+    fn test_reclaim_recently_active() {
+        let (mut contract, mut context) = setup_dormant();
+        empty_user1(&mut contract, &mut context);
+
+        reclaim_at(&mut contract, &mut context, 5_999);
+    }
+
+    #[test]
+    fn test_last_active_not_tracked_by_default() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        transfer_at(&mut contract, &mut context, 1_000);
+
+        assert!(!contract.is_activity_tracking_enabled());
+        assert_eq!(contract.last_active(owner()), None);
+        assert_eq!(contract.last_active(user1()), None);
+    }
+}
@@ -0,0 +1,416 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, env, near, require, AccountId};
+
+use crate::errors::{ERR_INSUFFICIENT_ALLOWANCE, ERR_QUERY_TOO_LARGE, ERR_SELF_APPROVAL};
+use crate::events::emit_event;
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{Contract, ContractExt};
+
+/// Data of the `approval` event emitted whenever an allowance is set or spent.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct Approval<'a> {
+    owner_id: &'a AccountId,
+    spender_id: &'a AccountId,
+    amount: U128,
+}
+
+#[near]
+impl Contract {
+    /// Allows `spender_id` to transfer up to `amount` of the caller's tokens with
+    /// `ft_transfer_from`. Replaces any previous allowance; zero revokes it.
+    #[payable]
+    pub fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != spender_id, ERR_SELF_APPROVAL);
+        self.assert_storage_funded(&owner_id);
+        self.internal_set_allowance(&owner_id, &spender_id, amount.0);
+        self.allowance_expiries.remove(&(owner_id, spender_id));
+    }
+
+    /// `ft_approve` for an allowance that lapses to zero at the block timestamp
+    /// `expires_at_ns`. Expired entries can be removed by anyone with
+    /// `cleanup_expired_allowances`.
+    #[payable]
+    pub fn ft_approve_with_expiry(
+        &mut self,
+        spender_id: AccountId,
+        amount: U128,
+        expires_at_ns: U64,
+    ) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != spender_id, ERR_SELF_APPROVAL);
+        self.assert_storage_funded(&owner_id);
+        self.internal_set_allowance(&owner_id, &spender_id, amount.0);
+        if amount.0 > 0 {
+            self.allowance_expiries
+                .insert(&(owner_id, spender_id), &expires_at_ns.0);
+        }
+    }
+
+    /// Returns the block timestamp at which the allowance of `spender_id` expires, if any.
+    pub fn ft_allowance_expiry(&self, owner_id: AccountId, spender_id: AccountId) -> Option<U64> {
+        self.allowance_expiries
+            .get(&(owner_id, spender_id))
+            .map(U64)
+    }
+
+    /// Removes the expired allowances `owner_id` gave to `spenders` to free their storage.
+    /// Anyone can call it, as only entries that no longer allow anything are removed.
+    /// Returns the number of removed allowances.
+    pub fn cleanup_expired_allowances(
+        &mut self,
+        owner_id: AccountId,
+        spenders: Vec<AccountId>,
+    ) -> u32 {
+        require!(
+            spenders.len() as u64 <= MAX_ITERATED_ACCOUNTS,
+            ERR_QUERY_TOO_LARGE
+        );
+        let now = env::block_timestamp();
+        let mut removed = 0;
+        for spender_id in spenders {
+            let key = (owner_id.clone(), spender_id);
+            if self
+                .allowance_expiries
+                .get(&key)
+                .is_some_and(|expires_at_ns| expires_at_ns <= now)
+            {
+                self.allowances.remove(&key);
+                self.allowance_expiries.remove(&key);
+                self.forget_allowance_spender(&owner_id, &key.1);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        self.internal_allowance(&owner_id, &spender_id).into()
+    }
+
+    /// Returns the allowance of each `(owner_id, spender_id)` pair, in the same order. At most
+    /// `MAX_ITERATED_ACCOUNTS` pairs can be queried per call.
+    pub fn ft_allowances_batch(&self, pairs: Vec<(AccountId, AccountId)>) -> Vec<U128> {
+        require!(
+            pairs.len() as u64 <= MAX_ITERATED_ACCOUNTS,
+            ERR_QUERY_TOO_LARGE
+        );
+        pairs
+            .iter()
+            .map(|(owner_id, spender_id)| self.internal_allowance(owner_id, spender_id).into())
+            .collect()
+    }
+
+    /// Returns how much `spender_id` can actually move from `owner_id` right now: the
+    /// allowance capped by the owner's balance.
+    pub fn ft_effective_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        let allowance = self.internal_allowance(&owner_id, &spender_id);
+        let balance = self.token.accounts.get(&owner_id).unwrap_or(0);
+        allowance.min(balance).into()
+    }
+
+    /// Transfers `amount` of `owner_id`'s tokens to `receiver_id`, spending the caller's
+    /// allowance.
+    #[payable]
+    pub fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let spender_id = env::predecessor_account_id();
+        let allowance = self.internal_allowance(&owner_id, &spender_id);
+        require!(allowance >= amount.0, ERR_INSUFFICIENT_ALLOWANCE);
+        self.internal_set_allowance(&owner_id, &spender_id, allowance - amount.0);
+        self.internal_transfer(&owner_id, &receiver_id, amount.0, memo);
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_allowance(
+        &self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+    ) -> Balance {
+        let key = (owner_id.clone(), spender_id.clone());
+        if self
+            .allowance_expiries
+            .get(&key)
+            .is_some_and(|expires_at_ns| expires_at_ns <= env::block_timestamp())
+        {
+            return 0;
+        }
+        self.allowances.get(&key).unwrap_or(0)
+    }
+
+    /// Stores the allowance, dropping the entry and its expiry altogether when it reaches
+    /// zero, and emits an `approval` event with the new amount.
+    pub(crate) fn internal_set_allowance(
+        &mut self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+        amount: Balance,
+    ) {
+        let key = (owner_id.clone(), spender_id.clone());
+        if amount == 0 {
+            self.allowances.remove(&key);
+            self.allowance_expiries.remove(&key);
+            self.forget_allowance_spender(owner_id, spender_id);
+        } else if self.allowances.insert(&key, &amount).is_none() {
+            let mut spenders = self.allowance_spenders.get(owner_id).unwrap_or_default();
+            spenders.push(spender_id.clone());
+            self.allowance_spenders.insert(owner_id, &spenders);
+        }
+        emit_event(
+            "approval",
+            Approval {
+                owner_id,
+                spender_id,
+                amount: amount.into(),
+            },
+        );
+    }
+
+    /// Removes every allowance `owner_id` gave, with their expiries.
+    pub(crate) fn internal_clear_allowances(&mut self, owner_id: &AccountId) {
+        for spender_id in self.allowance_spenders.remove(owner_id).unwrap_or_default() {
+            let key = (owner_id.clone(), spender_id);
+            self.allowances.remove(&key);
+            self.allowance_expiries.remove(&key);
+        }
+    }
+
+    /// Drops `spender_id` from the spenders `owner_id` gave an allowance to.
+    fn forget_allowance_spender(&mut self, owner_id: &AccountId, spender_id: &AccountId) {
+        if let Some(mut spenders) = self.allowance_spenders.get(owner_id) {
+            spenders.retain(|id| id != spender_id);
+            if spenders.is_empty() {
+                self.allowance_spenders.remove(owner_id);
+            } else {
+                self.allowance_spenders.insert(owner_id, &spenders);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    fn approve(contract: &mut Contract, context: &mut VMContextBuilder, amount: u128) {
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_approve(user2(), amount.into());
+    }
+
+    fn fund_user1(contract: &mut Contract, context: &mut VMContextBuilder, amount: u128) {
+        register_user(contract, context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), amount.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_from() {
+        let (mut contract, mut context) = setup();
+        fund_user1(&mut contract, &mut context, 1_000);
+        register_user(&mut contract, &mut context, user2());
+        approve(&mut contract, &mut context, 600);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_from(user1(), user2(), 400.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 600);
+        assert_eq!(contract.ft_balance_of(user2()).0, 400);
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 200);
+    }
+
+    fn approval_event(amount: u128) -> String {
+        format!(
+            r#"EVENT_JSON:{{"standard":"publicai","version":"1.0.0","event":"approval","data":[{{"owner_id":"{}","spender_id":"{}","amount":"{}"}}]}}"#,
+            user1(),
+            user2(),
+            amount
+        )
+    }
+
+    #[test]
+    fn test_approve_emits_event() {
+        let (mut contract, mut context) = setup();
+        approve(&mut contract, &mut context, 600);
+
+        assert_eq!(get_logs(), vec![approval_event(600)]);
+    }
+
+    #[test]
+    fn test_transfer_from_emits_event_with_remaining_allowance() {
+        let (mut contract, mut context) = setup();
+        fund_user1(&mut contract, &mut context, 1_000);
+        register_user(&mut contract, &mut context, user2());
+        approve(&mut contract, &mut context, 600);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_from(user1(), user2(), 600.into(), None);
+
+        let logs = get_logs();
+        assert_eq!(logs[0], approval_event(0));
+        assert!(logs[1].contains(r#""event":"ft_transfer""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient allowance")]
+    fn test_transfer_from_above_allowance() {
+        let (mut contract, mut context) = setup();
+        fund_user1(&mut contract, &mut context, 1_000);
+        register_user(&mut contract, &mut context, user2());
+        approve(&mut contract, &mut context, 100);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_from(user1(), user2(), 101.into(), None);
+    }
+
+    #[test]
+    fn test_effective_allowance_capped_by_balance() {
+        let (mut contract, mut context) = setup();
+        fund_user1(&mut contract, &mut context, 1_000);
+        approve(&mut contract, &mut context, 5_000);
+
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 5_000);
+        assert_eq!(contract.ft_effective_allowance(user1(), user2()).0, 1_000);
+    }
+
+    fn approve_until(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        spender_id: AccountId,
+        expires_at_ns: u64,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(0)
+            .build());
+        contract.ft_approve_with_expiry(spender_id, 500.into(), expires_at_ns.into());
+    }
+
+    #[test]
+    fn test_allowance_expires() {
+        let (mut contract, mut context) = setup();
+        approve_until(&mut contract, &mut context, user2(), 1_000);
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 500);
+        assert_eq!(
+            contract.ft_allowance_expiry(user1(), user2()),
+            Some(1_000.into())
+        );
+
+        testing_env!(context.block_timestamp(1_000).build());
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 0);
+    }
+
+    #[test]
+    fn test_cleanup_expired_allowances() {
+        let (mut contract, mut context) = setup();
+        let storage_before = env::storage_usage();
+        approve_until(&mut contract, &mut context, user2(), 1_000);
+        approve_until(&mut contract, &mut context, owner(), 5_000);
+        let storage_approved = env::storage_usage();
+        assert!(storage_approved > storage_before);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_yoctonear(0))
+            .block_timestamp(2_000)
+            .build());
+        // the allowance of owner() hasn't expired yet
+        assert_eq!(
+            contract.cleanup_expired_allowances(user1(), vec![user2(), owner()]),
+            1
+        );
+
+        assert!(env::storage_usage() < storage_approved);
+        assert_eq!(contract.ft_allowance_expiry(user1(), user2()), None);
+        assert_eq!(contract.ft_allowance(user1(), owner()).0, 500);
+        assert_eq!(
+            contract.cleanup_expired_allowances(user1(), vec![user2(), owner()]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_approve_without_expiry_clears_expiry() {
+        let (mut contract, mut context) = setup();
+        approve_until(&mut contract, &mut context, user2(), 1_000);
+        approve(&mut contract, &mut context, 300);
+
+        testing_env!(context.block_timestamp(2_000).build());
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 300);
+        assert_eq!(
+            contract.cleanup_expired_allowances(user1(), vec![user2()]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_allowances_batch() {
+        let (mut contract, mut context) = setup();
+        approve(&mut contract, &mut context, 300);
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_approve(owner(), 700.into());
+
+        assert_eq!(
+            contract.ft_allowances_batch(vec![
+                (user2(), owner()),
+                (user2(), user1()),
+                (user1(), user2()),
+            ]),
+            vec![U128(700), U128(0), U128(300)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many accounts requested")]
+    fn test_allowances_batch_too_large() {
+        let (contract, _) = setup();
+
+        let pairs = vec![(user1(), user2()); MAX_ITERATED_ACCOUNTS as usize + 1];
+        contract.ft_allowances_batch(pairs);
+    }
+
+    #[test]
+    fn test_effective_allowance_capped_by_allowance() {
+        let (mut contract, mut context) = setup();
+        fund_user1(&mut contract, &mut context, 1_000);
+        approve(&mut contract, &mut context, 300);
+
+        assert_eq!(contract.ft_effective_allowance(user1(), user2()).0, 300);
+        assert_eq!(contract.ft_effective_allowance(user2(), user1()).0, 0);
+    }
+}
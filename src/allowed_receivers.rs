@@ -0,0 +1,140 @@
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::{ERR_RECEIVER_NOT_ALLOWED, ERR_TOO_MANY_ALLOWED_RECEIVERS};
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Restricts the outgoing transfers of the caller to `receivers`, e.g. for a vault only
+    /// paying out to known accounts. An empty list lifts the restriction.
+    #[payable]
+    pub fn set_allowed_receivers(&mut self, receivers: Vec<AccountId>) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        self.internal_set_allowed_receivers(&account_id, receivers);
+    }
+
+    /// `set_allowed_receivers` on behalf of `account_id`, for vault accounts the owner sets up
+    /// and that are managed externally.
+    #[payable]
+    pub fn admin_set_allowed_receivers(
+        &mut self,
+        account_id: AccountId,
+        receivers: Vec<AccountId>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_set_allowed_receivers(&account_id, receivers);
+    }
+
+    /// Returns the only receivers `account_id` can transfer to, `None` if unrestricted.
+    pub fn get_allowed_receivers(&self, account_id: AccountId) -> Option<Vec<AccountId>> {
+        self.allowed_receivers.get(&account_id)
+    }
+}
+
+impl Contract {
+    fn internal_set_allowed_receivers(
+        &mut self,
+        account_id: &AccountId,
+        receivers: Vec<AccountId>,
+    ) {
+        require!(
+            receivers.len() as u64 <= MAX_ITERATED_ACCOUNTS,
+            ERR_TOO_MANY_ALLOWED_RECEIVERS
+        );
+        if receivers.is_empty() {
+            self.allowed_receivers.remove(account_id);
+        } else {
+            self.assert_storage_funded(account_id);
+            self.allowed_receivers.insert(account_id, &receivers);
+        }
+        log!("Allowed receivers of {} set to {:?}", account_id, receivers);
+    }
+
+    pub(crate) fn is_receiver_allowed(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+    ) -> bool {
+        self.allowed_receivers
+            .get(sender_id)
+            .is_none_or(|receivers| receivers.contains(receiver_id))
+    }
+
+    /// Panics if `sender_id` restricted its transfers to receivers other than `receiver_id`.
+    pub(crate) fn assert_receiver_allowed(&self, sender_id: &AccountId, receiver_id: &AccountId) {
+        require!(
+            self.is_receiver_allowed(sender_id, receiver_id),
+            ERR_RECEIVER_NOT_ALLOWED
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    fn vault() -> AccountId {
+        accounts(4)
+    }
+
+    /// Funds the vault with 1_000 tokens and lets the owner restrict it to user1.
+    fn setup_vault() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, vault());
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(vault(), 1_000.into(), None);
+        contract.admin_set_allowed_receivers(vault(), vec![user1()]);
+        testing_env!(context.predecessor_account_id(vault()).build());
+        (contract, context)
+    }
+
+    #[test]
+    fn test_owner_seeds_allowed_receivers() {
+        let (mut contract, _) = setup_vault();
+        assert_eq!(contract.get_allowed_receivers(vault()), Some(vec![user1()]));
+
+        contract.ft_transfer(user1(), 100.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver is not in the allowed receivers of the sender")]
+    fn test_transfer_to_receiver_not_allowed() {
+        let (mut contract, _) = setup_vault();
+
+        contract.ft_transfer(user2(), 100.into(), None);
+    }
+
+    #[test]
+    fn test_clear_allowed_receivers() {
+        let (mut contract, _) = setup_vault();
+
+        contract.set_allowed_receivers(vec![]);
+        contract.ft_transfer(user2(), 100.into(), None);
+
+        assert_eq!(contract.get_allowed_receivers(vault()), None);
+        assert_eq!(contract.ft_balance_of(user2()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_admin_set_allowed_receivers_only_owner() {
+        let (mut contract, _) = setup_vault();
+
+        contract.admin_set_allowed_receivers(user1(), vec![user2()]);
+    }
+}
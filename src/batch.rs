@@ -0,0 +1,287 @@
+use near_contract_standards::storage_management::StorageManagement;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, NearToken, Promise};
+
+use crate::errors::{
+    ERR_BATCH_LENGTH_MISMATCH, ERR_BATCH_TOO_LARGE, ERR_EMPTY_BATCH,
+    ERR_INSUFFICIENT_STORAGE_DEPOSIT, ERR_INVALID_BATCH_SIZE, ERR_REQUIRES_DEPOSIT,
+};
+use crate::{emit_transfers, Contract, ContractExt};
+
+/// Number of entries a batch method accepts until the owner tunes it.
+pub const DEFAULT_MAX_BATCH_SIZE: u32 = 100;
+
+#[near]
+impl Contract {
+    /// Sets the maximum number of entries accepted by `ft_transfer_batch` and `mint_batch`,
+    /// to keep batches within the gas limit.
+    #[payable]
+    pub fn set_max_batch_size(&mut self, max_batch_size: u32) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(max_batch_size > 0, ERR_INVALID_BATCH_SIZE);
+        log!("Max batch size set to {}", max_batch_size);
+        self.max_batch_size = max_batch_size;
+    }
+
+    pub fn get_max_batch_size(&self) -> u32 {
+        self.max_batch_size
+    }
+
+    /// Transfers `amounts[i]` to `receiver_ids[i]` for every recipient. All transfers are
+    /// reported in a single `FtTransfer` event, one entry per credited account.
+    #[payable]
+    pub fn ft_transfer_batch(
+        &mut self,
+        receiver_ids: Vec<AccountId>,
+        amounts: Vec<U128>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        require!(!receiver_ids.is_empty(), ERR_EMPTY_BATCH);
+        self.assert_batch_size(receiver_ids.len());
+        require!(
+            receiver_ids.len() == amounts.len(),
+            ERR_BATCH_LENGTH_MISMATCH
+        );
+        let sender_id = env::predecessor_account_id();
+        self.assert_not_paused();
+        self.assert_not_frozen(&sender_id);
+        self.assert_not_self_locked(&sender_id);
+        self.record_memo(&sender_id, memo.as_deref());
+
+        let mut legs = Vec::with_capacity(receiver_ids.len());
+        for (receiver_id, amount) in receiver_ids.iter().zip(amounts) {
+            self.assert_receiver_allowed(&sender_id, receiver_id);
+            self.assert_min_remaining(&sender_id, amount.0);
+            legs.extend(self.internal_move(&sender_id, receiver_id, amount.into(), memo.clone()));
+        }
+        emit_transfers(&sender_id, &legs);
+    }
+
+    /// Registers every account of a sponsored cohort that isn't registered yet, paying its
+    /// storage from the attached deposit, and transfers it its amount from the treasury.
+    /// Either every account is onboarded or none is. The unused deposit is refunded.
+    #[payable]
+    pub fn onboard_accounts(&mut self, accounts: Vec<(AccountId, U128)>) {
+        self.assert_owner();
+        require!(
+            env::attached_deposit() >= NearToken::from_yoctonear(1),
+            ERR_REQUIRES_DEPOSIT
+        );
+        require!(!accounts.is_empty(), ERR_EMPTY_BATCH);
+        self.assert_batch_size(accounts.len());
+        self.assert_not_paused();
+
+        let min_balance = self.storage_balance_bounds().min;
+        let mut deposit = env::attached_deposit();
+        let treasury_id = self.treasury_id.clone();
+        let mut legs = Vec::with_capacity(accounts.len());
+        for (account_id, amount) in &accounts {
+            if !self.token.accounts.contains_key(account_id) {
+                deposit = deposit
+                    .checked_sub(min_balance)
+                    .unwrap_or_else(|| env::panic_str(ERR_INSUFFICIENT_STORAGE_DEPOSIT));
+                self.internal_register_account(account_id);
+            }
+            legs.extend(self.internal_move(&treasury_id, account_id, amount.0, None));
+        }
+        emit_transfers(&treasury_id, &legs);
+        log!("Onboarded {} accounts", accounts.len());
+        if !deposit.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(deposit);
+        }
+    }
+
+    /// Returns the deposit to attach to `onboard_accounts` for `count` accounts, assuming
+    /// none of them is registered yet. The storage of registered accounts is refunded.
+    pub fn onboarding_cost(&self, count: u64) -> NearToken {
+        self.storage_balance_bounds()
+            .min
+            .saturating_mul(u128::from(count))
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_batch_size(&self, len: usize) {
+        require!(len <= self.max_batch_size as usize, ERR_BATCH_TOO_LARGE);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    #[test]
+    fn test_transfer_batch_emits_single_event() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        register_user(&mut contract, &mut context, accounts(4));
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_batch(
+            vec![user1(), user2(), accounts(4)],
+            vec![U128(100), U128(200), U128(300)],
+            Some("airdrop".to_string()),
+        );
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+        assert_eq!(contract.ft_balance_of(user2()).0, 200);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 300);
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY - 600);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            logs[0],
+            format!(
+                r#"EVENT_JSON:{{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{{"old_owner_id":"{owner}","new_owner_id":"{}","amount":"100","memo":"airdrop"}},{{"old_owner_id":"{owner}","new_owner_id":"{}","amount":"200","memo":"airdrop"}},{{"old_owner_id":"{owner}","new_owner_id":"{}","amount":"300","memo":"airdrop"}}]}}"#,
+                user1(),
+                user2(),
+                accounts(4),
+                owner = owner(),
+            )
+        );
+    }
+
+    fn set_max_batch_size(contract: &mut Contract, context: &mut VMContextBuilder, size: u32) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_max_batch_size(size);
+    }
+
+    #[test]
+    fn test_transfer_batch_at_max_size() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        set_max_batch_size(&mut contract, &mut context, 2);
+
+        contract.ft_transfer_batch(vec![user1(), user2()], vec![U128(100), U128(200)], None);
+
+        assert_eq!(contract.get_max_batch_size(), 2);
+        assert_eq!(contract.ft_balance_of(user2()).0, 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch too large")]
+    fn test_transfer_batch_above_max_size() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        set_max_batch_size(&mut contract, &mut context, 1);
+
+        contract.ft_transfer_batch(vec![user1(), user2()], vec![U128(100), U128(200)], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch too large")]
+    fn test_mint_batch_above_max_size() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        set_max_batch_size(&mut contract, &mut context, 1);
+        contract.add_minter(owner());
+
+        contract.mint_batch(vec![(user1(), U128(100)), (user2(), U128(200))]);
+    }
+
+    fn onboard(contract: &mut Contract, context: &mut VMContextBuilder, deposit: NearToken) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(deposit)
+            .build());
+        contract.onboard_accounts(vec![
+            (user1(), U128(100)),
+            (user2(), U128(200)),
+            (accounts(4), U128(300)),
+        ]);
+    }
+
+    #[test]
+    fn test_onboard_accounts() {
+        let (mut contract, mut context) = setup();
+        // an already registered account doesn't pay storage again
+        register_user(&mut contract, &mut context, user1());
+        let min_balance = contract.storage_balance_bounds().min;
+
+        let cost = contract.onboarding_cost(3);
+        onboard(&mut contract, &mut context, cost);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+        assert_eq!(contract.ft_balance_of(user2()).0, 200);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, 300);
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY - 600);
+        assert!(contract.storage_balance_of(accounts(4)).is_some());
+        // the storage of user1 is refunded
+        let refunds: Vec<_> = get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .collect();
+        assert_eq!(
+            refunds,
+            vec![MockAction::Transfer {
+                receipt_index: 0,
+                deposit: min_balance
+            }]
+        );
+    }
+
+    #[test]
+    fn test_onboarding_cost() {
+        let (contract, _) = setup();
+        let min_balance = contract.storage_balance_bounds().min;
+
+        assert_eq!(contract.onboarding_cost(0), NearToken::from_yoctonear(0));
+        assert_eq!(contract.onboarding_cost(1), min_balance);
+        assert_eq!(contract.onboarding_cost(25), min_balance.saturating_mul(25));
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_onboard_accounts_insufficient_deposit() {
+        let (mut contract, mut context) = setup();
+        let min_balance = contract.storage_balance_bounds().min;
+
+        onboard(&mut contract, &mut context, min_balance.saturating_mul(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Receivers and amounts should have the same length")]
+    fn test_transfer_batch_panics_on_length_mismatch() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_batch(vec![user1()], vec![U128(100), U128(200)], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_transfer_batch_panics_on_non_registered_receiver() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_batch(vec![user1(), user2()], vec![U128(100), U128(200)], None);
+    }
+}
@@ -0,0 +1,187 @@
+use near_contract_standards::fungible_token::events::FtBurn;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::{ERR_NO_BURN_ADDRESS, ERR_ZERO_AMOUNT};
+use crate::events::emit_event;
+use crate::{Contract, ContractExt};
+
+/// Data of the `redemption` event emitted by `redeem`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct Redemption<'a> {
+    account_id: &'a AccountId,
+    amount: U128,
+}
+
+#[near]
+impl Contract {
+    /// Burns `amount` of the caller's tokens for the buyback program. The payout is made
+    /// off-chain by a process watching the `redemption` events.
+    #[payable]
+    pub fn redeem(&mut self, amount: U128) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        self.assert_not_paused();
+        self.assert_not_frozen(&account_id);
+        self.assert_not_self_locked(&account_id);
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        self.assert_unlocked(&account_id, amount.0);
+        self.internal_debit(&account_id, amount.0);
+        self.record_burned(amount.0);
+        FtBurn {
+            owner_id: &account_id,
+            amount,
+            memo: Some("redemption"),
+        }
+        .emit();
+        emit_event(
+            "redemption",
+            Redemption {
+                account_id: &account_id,
+                amount,
+            },
+        );
+    }
+
+    /// Sets the well-known dead account `burn_by_transfer` sends tokens to, or removes it
+    /// with `None`. The account is registered if needed and excluded from the circulating
+    /// supply. Transfers to it are never charged fees.
+    #[payable]
+    pub fn set_burn_address(&mut self, burn_address: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(burn_address) = &burn_address {
+            if !self.token.accounts.contains_key(burn_address) {
+                self.internal_register_account(burn_address);
+            }
+            self.excluded_accounts.insert(burn_address);
+        }
+        log!("Burn address set to {:?}", burn_address);
+        self.burn_address = burn_address;
+    }
+
+    pub fn get_burn_address(&self) -> Option<AccountId> {
+        self.burn_address.clone()
+    }
+
+    /// Burns `amount` of the caller's tokens by sending them to the burn address, which
+    /// keeps the total supply unchanged but lowers the circulating supply.
+    #[payable]
+    pub fn burn_by_transfer(&mut self, amount: U128) {
+        assert_one_yocto();
+        let burn_address = self
+            .burn_address
+            .clone()
+            .unwrap_or_else(|| env::panic_str(ERR_NO_BURN_ADDRESS));
+        let account_id = env::predecessor_account_id();
+        self.internal_transfer(
+            &account_id,
+            &burn_address,
+            amount.0,
+            Some("burn".to_string()),
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, TOTAL_SUPPLY};
+
+    /// Funds user1 with 1_000 tokens and makes it the caller.
+    fn setup_holder(contract: &mut Contract, context: &mut VMContextBuilder) {
+        register_user(contract, context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+    }
+
+    #[test]
+    fn test_redeem() {
+        let (mut contract, mut context) = setup();
+        setup_holder(&mut contract, &mut context);
+
+        contract.redeem(400.into());
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 600);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 400);
+        let logs = get_logs();
+        assert!(logs[0].contains(r#""event":"ft_burn""#));
+        assert_eq!(
+            logs[1],
+            format!(
+                r#"EVENT_JSON:{{"standard":"publicai","version":"1.0.0","event":"redemption","data":[{{"account_id":"{}","amount":"400"}}]}}"#,
+                user1()
+            )
+        );
+    }
+
+    fn burn_address() -> AccountId {
+        "dead.near".parse().unwrap()
+    }
+
+    #[test]
+    fn test_burn_by_transfer() {
+        let (mut contract, mut context) = setup();
+        setup_holder(&mut contract, &mut context);
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_burn_address(Some(burn_address()));
+        contract.set_transfer_fee(100);
+        let circulating_supply = contract.ft_circulating_supply().0;
+
+        testing_env!(context.predecessor_account_id(user1()).build());
+        contract.burn_by_transfer(400.into());
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 600);
+        assert_eq!(contract.ft_balance_of(burn_address()).0, 400);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_circulating_supply().0, circulating_supply - 400);
+        assert_eq!(
+            contract.get_excluded_accounts(None, None),
+            vec![burn_address()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Burn address is not set")]
+    fn test_burn_by_transfer_without_burn_address() {
+        let (mut contract, mut context) = setup();
+        setup_holder(&mut contract, &mut context);
+
+        contract.burn_by_transfer(400.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_redeem_above_balance() {
+        let (mut contract, mut context) = setup();
+        setup_holder(&mut contract, &mut context);
+
+        contract.redeem(1_001.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_redeem_requires_one_yocto() {
+        let (mut contract, mut context) = setup();
+        setup_holder(&mut contract, &mut context);
+
+        testing_env!(context.attached_deposit(NearToken::from_near(0)).build());
+        contract.redeem(400.into());
+    }
+}
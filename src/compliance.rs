@@ -0,0 +1,308 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, log, near, require, AccountId};
+
+use crate::errors::{ERR_ACCOUNT_FROZEN, ERR_NOTHING_TO_SEIZE, ERR_SELF_TRANSFER, ERR_ZERO_AMOUNT};
+use crate::events::emit_event;
+use crate::supply::paginate_accounts;
+use crate::{emit_transfers, Contract, ContractExt, TransferLeg};
+
+/// Data of the `seizure` event emitted by `admin_force_transfer`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct Seizure<'a> {
+    authorized_id: &'a AccountId,
+    old_owner_id: &'a AccountId,
+    new_owner_id: &'a AccountId,
+    amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memo: Option<&'a str>,
+}
+
+#[near]
+impl Contract {
+    /// Freezes `account_id`: it can still receive tokens but can't send them anymore.
+    #[payable]
+    pub fn freeze_account(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Account {} frozen", account_id);
+        self.frozen_accounts.insert(&account_id)
+    }
+
+    #[payable]
+    pub fn unfreeze_account(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Account {} unfrozen", account_id);
+        self.frozen_accounts.remove(&account_id)
+    }
+
+    pub fn is_frozen(&self, account_id: AccountId) -> bool {
+        self.frozen_accounts.contains(&account_id)
+    }
+
+    /// Returns a page of the frozen accounts, at most `MAX_ITERATED_ACCOUNTS` long.
+    pub fn get_frozen_accounts(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        paginate_accounts(&self.frozen_accounts, from_index, limit)
+    }
+
+    /// Moves `amount` out of `from` regardless of it being frozen or locked, e.g. to execute
+    /// a court order, free of transfer fees. Besides the regular `ft_transfer` event, a
+    /// `seizure` event records the move.
+    #[payable]
+    pub fn admin_force_transfer(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_seize(&from, &to, amount.0, memo);
+    }
+
+    /// Moves the whole balance of a confirmed malicious `account_id` to the treasury and
+    /// freezes it. Any balance lock of the account is released first, as the seizure
+    /// overrides it. Returns the seized amount.
+    #[payable]
+    pub fn admin_seize_all(&mut self, account_id: AccountId) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        require!(balance > 0, ERR_NOTHING_TO_SEIZE);
+        self.balance_locks.remove(&account_id);
+        let treasury_id = self.treasury_id.clone();
+        self.internal_seize(
+            &account_id,
+            &treasury_id,
+            balance,
+            Some("full seizure".to_string()),
+        );
+        self.frozen_accounts.insert(&account_id);
+        log!("Account {} frozen after a full seizure", account_id);
+        balance.into()
+    }
+}
+
+impl Contract {
+    /// Moves `amount` from `from` to `to` on the owner's authority, reporting it with both the
+    /// regular `ft_transfer` event and a `seizure` event. No fee, burn or reward share is
+    /// charged and balance locks don't apply, so `to` is credited the whole amount.
+    fn internal_seize(
+        &mut self,
+        from: &AccountId,
+        to: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        log!(
+            "Owner {} seizes {} from {} to {}",
+            self.owner_id,
+            amount,
+            from,
+            to
+        );
+        require!(from != to, ERR_SELF_TRANSFER);
+        require!(amount > 0, ERR_ZERO_AMOUNT);
+        self.internal_debit(from, amount);
+        self.internal_credit(to, amount);
+        self.notify_transfer_observer(from, to, amount);
+        self.check_balance_zeroed(from);
+        emit_transfers(
+            from,
+            &[TransferLeg {
+                receiver_id: to.clone(),
+                amount,
+                memo: memo.clone(),
+            }],
+        );
+        emit_event(
+            "seizure",
+            Seizure {
+                authorized_id: &self.owner_id,
+                old_owner_id: from,
+                new_owner_id: to,
+                amount: amount.into(),
+                memo: memo.as_deref(),
+            },
+        );
+    }
+
+    pub(crate) fn assert_not_frozen(&self, account_id: &AccountId) {
+        require!(
+            !self.frozen_accounts.contains(account_id),
+            ERR_ACCOUNT_FROZEN
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::supply::MAX_ITERATED_ACCOUNTS;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Funds user1 with 1_000 tokens and freezes it.
+    fn setup_frozen(contract: &mut Contract, context: &mut VMContextBuilder) {
+        register_user(contract, context, user1());
+        register_user(contract, context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.freeze_account(user1());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is frozen")]
+    fn test_frozen_account_cant_transfer() {
+        let (mut contract, mut context) = setup();
+        setup_frozen(&mut contract, &mut context);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user2(), 100.into(), None);
+    }
+
+    #[test]
+    fn test_admin_force_transfer_from_frozen_account() {
+        let (mut contract, mut context) = setup();
+        setup_frozen(&mut contract, &mut context);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.admin_force_transfer(user1(), user2(), 400.into(), Some("order #7".to_string()));
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 600);
+        assert_eq!(contract.ft_balance_of(user2()).0, 400);
+        assert!(contract.is_frozen(user1()));
+
+        let seizure = get_logs()
+            .into_iter()
+            .find(|log| log.contains(r#""event":"seizure""#))
+            .expect("seizure event");
+        assert!(seizure.starts_with(r#"EVENT_JSON:{"standard":"publicai""#));
+        assert!(seizure.contains(&format!(r#""old_owner_id":"{}""#, user1())));
+        assert!(seizure.contains(&format!(r#""new_owner_id":"{}""#, user2())));
+        assert!(seizure.contains(r#""amount":"400""#));
+        assert!(seizure.contains(r#""memo":"order #7""#));
+    }
+
+    #[test]
+    fn test_admin_force_transfer_is_fee_free_and_ignores_locks() {
+        let (mut contract, mut context) = setup();
+        setup_frozen(&mut contract, &mut context);
+        contract.set_transfer_fee(100);
+        contract.set_transfer_burn(100);
+        contract.lock_balance(user1(), 1_000.into(), u64::MAX.into());
+        let treasury_before = contract.ft_balance_of(owner()).0;
+
+        contract.admin_force_transfer(user1(), user2(), 1_000.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(owner()).0, treasury_before);
+        let seizure = get_logs()
+            .into_iter()
+            .find(|log| log.contains(r#""event":"seizure""#))
+            .expect("seizure event");
+        assert!(seizure.contains(r#""amount":"1000""#));
+    }
+
+    #[test]
+    fn test_admin_seize_all() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.lock_balance(user1(), 500.into(), u64::MAX.into());
+        let treasury_before = contract.ft_balance_of(owner()).0;
+
+        assert_eq!(contract.admin_seize_all(user1()).0, 1_000);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+        assert_eq!(contract.ft_balance_of(owner()).0, treasury_before + 1_000);
+        assert!(contract.is_frozen(user1()));
+        let seizure = get_logs()
+            .into_iter()
+            .find(|log| log.contains(r#""event":"seizure""#))
+            .expect("seizure event");
+        assert!(seizure.contains(&format!(r#""old_owner_id":"{}""#, user1())));
+        assert!(seizure.contains(&format!(r#""new_owner_id":"{}""#, owner())));
+        assert!(seizure.contains(r#""amount":"1000""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account has no balance to seize")]
+    fn test_admin_seize_all_empty_account() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.admin_seize_all(user1());
+    }
+
+    #[test]
+    fn test_get_frozen_accounts_paginated() {
+        let (mut contract, mut context) = setup();
+        let frozen: Vec<AccountId> = (0..5)
+            .map(|i| format!("frozen-{}.near", i).parse().unwrap())
+            .collect();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        for account_id in &frozen {
+            contract.freeze_account(account_id.clone());
+        }
+
+        assert_eq!(contract.get_frozen_accounts(None, Some(2)), frozen[..2]);
+        assert_eq!(contract.get_frozen_accounts(Some(2), Some(2)), frozen[2..4]);
+        assert_eq!(contract.get_frozen_accounts(Some(4), None), frozen[4..]);
+        assert!(contract.get_frozen_accounts(Some(5), None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many accounts requested")]
+    fn test_get_frozen_accounts_page_too_large() {
+        let (contract, _) = setup();
+
+        contract.get_frozen_accounts(None, Some(MAX_ITERATED_ACCOUNTS + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_admin_force_transfer_only_owner() {
+        let (mut contract, mut context) = setup();
+        setup_frozen(&mut contract, &mut context);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.admin_force_transfer(user1(), user2(), 400.into(), None);
+    }
+}
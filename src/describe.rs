@@ -0,0 +1,74 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadataProvider;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::{json, Value};
+use near_sdk::{near, AccountId};
+
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Returns the whole current state of the token in one nested JSON object, for explorers
+    /// and generated UIs: `metadata`, `config`, `limits`, `access_control` and `supply`.
+    /// Lists are truncated to their first `MAX_ITERATED_ACCOUNTS` entries, and the
+    /// circulating supply is `null` when there are too many excluded accounts to sum.
+    pub fn describe(&self) -> Value {
+        let circulating_supply = (self.excluded_accounts.len() <= MAX_ITERATED_ACCOUNTS)
+            .then(|| self.ft_circulating_supply());
+        let minters: Vec<AccountId> = self.get_minters(None, None);
+        json!({
+            "metadata": self.ft_metadata(),
+            "config": self.get_config(),
+            "limits": self.get_limits(),
+            "access_control": {
+                "owner_id": self.owner_id,
+                "minters": minters,
+                "paused": self.paused,
+                "pause_reason": self.pause_reason,
+                "minting_paused": self.minting_paused,
+                "finalized": self.finalized,
+                "metadata_locked": self.metadata_locked,
+            },
+            "supply": {
+                "total_supply": U128(self.token.total_supply),
+                "circulating_supply": circulating_supply,
+                "max_supply": self.max_supply.map(U128),
+                "holders": self.holders.len(),
+                "holders_with_balance": self.nonzero_holders,
+            },
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use crate::tests::{owner, setup, TOTAL_SUPPLY};
+
+    #[test]
+    fn test_describe() {
+        let (contract, _) = setup();
+
+        let description = contract.describe();
+
+        for key in ["metadata", "config", "limits", "access_control", "supply"] {
+            assert!(
+                description[key].is_object(),
+                "{} is missing or not an object",
+                key
+            );
+        }
+        assert_eq!(description["metadata"]["symbol"], "EXAMPLE");
+        assert_eq!(description["config"]["owner_id"], owner().as_str());
+        assert_eq!(description["limits"]["max_batch_size"], 100);
+        assert_eq!(description["access_control"]["paused"], false);
+        assert_eq!(
+            description["supply"]["total_supply"],
+            TOTAL_SUPPLY.to_string()
+        );
+        assert_eq!(
+            description["supply"]["circulating_supply"],
+            TOTAL_SUPPLY.to_string()
+        );
+        assert_eq!(description["supply"]["holders"], 1);
+    }
+}
@@ -0,0 +1,100 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadataProvider;
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{near, AccountId};
+
+use crate::{Contract, ContractExt};
+
+/// A balance together with its human-readable form, returned by `ft_balance_display`.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub struct BalanceDisplay {
+    pub raw: U128,
+    /// The balance in whole tokens according to the metadata decimals, e.g. `"1.5"`.
+    pub formatted: String,
+}
+
+#[near]
+impl Contract {
+    pub fn ft_balance_display(&self, account_id: AccountId) -> BalanceDisplay {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        BalanceDisplay {
+            raw: balance.into(),
+            formatted: format_amount(balance, self.ft_metadata().decimals),
+        }
+    }
+}
+
+/// Formats `amount` of the smallest units as a decimal number of whole tokens, without
+/// trailing zeros in the fractional part.
+pub(crate) fn format_amount(amount: Balance, decimals: u8) -> String {
+    let digits = format!("{:0>width$}", amount, width = usize::from(decimals) + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - usize::from(decimals));
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    const ONE_TOKEN: Balance = 10u128.pow(24);
+
+    #[test]
+    fn test_balance_display_whole_tokens() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        contract.token.internal_deposit(&user1(), 2 * ONE_TOKEN);
+
+        assert_eq!(
+            contract.ft_balance_display(user1()),
+            BalanceDisplay {
+                raw: (2 * ONE_TOKEN).into(),
+                formatted: "2".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_balance_display_fractional() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        contract
+            .token
+            .internal_deposit(&user1(), ONE_TOKEN + ONE_TOKEN / 2);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user2(), 1.into(), None);
+
+        assert_eq!(contract.ft_balance_display(user1()).formatted, "1.5");
+        assert_eq!(
+            contract.ft_balance_display(user2()).formatted,
+            "0.000000000000000000000001"
+        );
+        assert_eq!(
+            contract.ft_balance_display("nobody.near".parse().unwrap()),
+            BalanceDisplay {
+                raw: 0.into(),
+                formatted: "0".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(1_234_500, 3), "1234.5");
+        assert_eq!(format_amount(42, 0), "42");
+    }
+}
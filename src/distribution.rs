@@ -0,0 +1,128 @@
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, near, require};
+
+use crate::errors::{ERR_INVALID_BUCKETS, ERR_TOO_MANY_HOLDERS};
+use crate::{Contract, ContractExt};
+
+/// Maximum number of holders `compute_distribution` iterates over. Reading a balance costs
+/// a storage read per holder, so larger sets would run out of gas.
+pub const MAX_DISTRIBUTION_HOLDERS: u64 = 1_000;
+
+#[near]
+impl Contract {
+    /// Bins every holder with a positive balance into `buckets`, given as strictly ascending
+    /// lower bounds, and stores the counts for `get_distribution`. Holders below the first
+    /// bound aren't counted. Panics if there are more than `MAX_DISTRIBUTION_HOLDERS`
+    /// registered accounts. Accounts registered before the holder set was introduced aren't
+    /// tracked.
+    #[payable]
+    pub fn compute_distribution(&mut self, buckets: Vec<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            !buckets.is_empty() && buckets.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            ERR_INVALID_BUCKETS
+        );
+        require!(
+            self.holders.len() <= MAX_DISTRIBUTION_HOLDERS,
+            ERR_TOO_MANY_HOLDERS
+        );
+
+        let mut counts = vec![0u64; buckets.len()];
+        for account_id in self.holders.iter() {
+            let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+            if balance == 0 {
+                continue;
+            }
+            if let Some(index) = buckets.iter().rposition(|bound| bound.0 <= balance) {
+                counts[index] += 1;
+            }
+        }
+        self.distribution = buckets.iter().map(|bound| bound.0).zip(counts).collect();
+    }
+
+    /// Returns the holder count of each bucket, keyed by its lower bound, as of the last
+    /// `compute_distribution`.
+    pub fn get_distribution(&self) -> Vec<(U128, u64)> {
+        self.distribution
+            .iter()
+            .map(|(bound, count)| (U128(*bound), *count))
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, AccountId, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    fn compute(contract: &mut Contract, context: &mut VMContextBuilder, buckets: Vec<u128>) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.compute_distribution(buckets.into_iter().map(U128).collect());
+    }
+
+    fn fund(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        account_id: AccountId,
+        amount: u128,
+    ) {
+        register_user(contract, context, account_id.clone());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(account_id, amount.into(), None);
+    }
+
+    #[test]
+    fn test_compute_distribution() {
+        let (mut contract, mut context) = setup();
+        fund(&mut contract, &mut context, user1(), 5);
+        fund(&mut contract, &mut context, user2(), 50);
+        fund(&mut contract, &mut context, accounts(4), 500);
+        fund(&mut contract, &mut context, accounts(5), 99);
+        // registered without balance: not a holder
+        register_user(&mut contract, &mut context, accounts(0));
+
+        compute(&mut contract, &mut context, vec![10, 100, 1_000]);
+
+        // user1 is below the first bucket, the owner holds the rest of the supply
+        assert_eq!(
+            contract.get_distribution(),
+            vec![(U128(10), 2), (U128(100), 1), (U128(1_000), 1)]
+        );
+    }
+
+    #[test]
+    fn test_unregistered_accounts_leave_distribution() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        contract.token.internal_deposit(&user1(), 50);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.storage_unregister(Some(true));
+        compute(&mut contract, &mut context, vec![1]);
+
+        assert_eq!(contract.get_distribution(), vec![(U128(1), 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Buckets must be non-empty and strictly ascending")]
+    fn test_compute_distribution_unsorted_buckets() {
+        let (mut contract, mut context) = setup();
+
+        compute(&mut contract, &mut context, vec![100, 10]);
+    }
+}
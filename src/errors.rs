@@ -0,0 +1,109 @@
+//! Panic messages of the contract. Clients and tests match on these strings, so they must
+//! not change once released.
+
+pub const ERR_ALREADY_INITIALIZED: &str = "Already initialized";
+pub const ERR_NOT_OWNER: &str = "Owner's method";
+pub const ERR_NOT_MINTER: &str = "Minter's method";
+pub const ERR_FINALIZED: &str = "Contract finalized";
+pub const ERR_PAUSED: &str = "Contract paused";
+pub const ERR_MINTING_PAUSED: &str = "Minting paused";
+pub const ERR_EMPTY_OWNER: &str = "New owner cannot be empty";
+pub const ERR_CONTRACT_ACCOUNT: &str = "The token contract account can't be used here";
+pub const ERR_EMPTY_MINTER: &str = "Minter cannot be empty";
+pub const ERR_DECIMALS_CHANGED: &str = "Can't change decimals";
+pub const ERR_METADATA_NOT_SET: &str = "Token metadata is not set";
+pub const ERR_INVALID_SPEC: &str = "Metadata spec must look like ft-1.0.0";
+pub const ERR_INVALID_LEGAL_REFERENCE_HASH: &str = "Legal reference hash must be 32 bytes";
+pub const ERR_METADATA_COOLDOWN: &str =
+    "Name and symbol can't change again before the cooldown ends";
+pub const ERR_METADATA_LOCKED: &str = "Metadata is locked";
+pub const ERR_NO_SCHEDULED_METADATA: &str = "No metadata update is scheduled";
+pub const ERR_SCHEDULED_METADATA_NOT_EFFECTIVE: &str = "Scheduled metadata is not effective yet";
+pub const ERR_STORAGE_INITIALIZED: &str = "Storage already initialized";
+pub const ERR_MIGRATION_ENDED: &str = "Migration has ended";
+
+pub const ERR_SELF_TRANSFER: &str = "Cannot transfer to self";
+pub const ERR_DEADLINE_PASSED: &str = "Transfer deadline passed";
+pub const ERR_ZERO_AMOUNT: &str = "The amount should be a positive number";
+pub const ERR_AMOUNT_PRECISION: &str = "Transfer amount has more precision than allowed";
+pub const ERR_PRECISION_TOO_HIGH: &str = "Transfer precision can't exceed the token decimals";
+pub const ERR_EMPTY_BATCH: &str = "Batch is empty";
+pub const ERR_BATCH_TOO_LARGE: &str = "Batch too large";
+pub const ERR_INVALID_BATCH_SIZE: &str = "Max batch size must be positive";
+pub const ERR_BATCH_LENGTH_MISMATCH: &str = "Receivers and amounts should have the same length";
+pub const ERR_TOO_MANY_ACCOUNTS: &str =
+    "Too many accounts for this operation; use paginated variant";
+pub const ERR_QUERY_TOO_LARGE: &str = "Too many accounts requested";
+pub const ERR_TOO_MANY_HOLDERS: &str = "Too many holders to compute the distribution in one call";
+pub const ERR_INVALID_BUCKETS: &str = "Buckets must be non-empty and strictly ascending";
+
+pub const ERR_FEE_TOO_HIGH: &str = "Transfer fee is too high";
+pub const ERR_BURN_TOO_HIGH: &str = "Transfer burn is too high";
+pub const ERR_REWARD_TOO_HIGH: &str = "Transfer reward is too high";
+pub const ERR_NO_REWARD_POOL: &str = "No reward pool is set";
+pub const ERR_PROTECTED_ACCOUNT: &str =
+    "The treasury, the reward pool and the burn address can't be unregistered";
+pub const ERR_MAX_SUPPLY_EXCEEDED: &str = "Max supply exceeded";
+pub const ERR_MAX_SUPPLY_BELOW_TOTAL_SUPPLY: &str = "Max supply is below the total supply";
+pub const ERR_SUPPLY_WARNING_TOO_HIGH: &str = "Supply warning threshold can't exceed 10000 bps";
+pub const ERR_NOTHING_TO_RECOVER: &str = "Not enough recoverable tokens";
+pub const ERR_NO_BURN_ADDRESS: &str = "Burn address is not set";
+pub const ERR_SUPPLY_OVERFLOW: &str = "Total supply overflow";
+pub const ERR_BALANCE_OVERFLOW: &str = "Balance overflow";
+pub const ERR_BALANCE_UNDERFLOW: &str = "Balance underflow";
+pub const ERR_INVALID_REBASE_RATIO: &str = "Rebase ratio must be positive";
+pub const ERR_REBASE_OVERFLOW: &str = "Rebase overflows the balance type";
+pub const ERR_REBASE_IN_PROGRESS: &str = "A rebase is in progress";
+pub const ERR_NO_REBASE_IN_PROGRESS: &str = "No rebase in progress";
+
+pub const ERR_VOLUME_CAP_EXCEEDED: &str = "Transfer volume cap of this epoch exceeded";
+
+pub const ERR_ACCOUNT_FROZEN: &str = "Account is frozen";
+pub const ERR_SELF_LOCKED: &str = "Outgoing transfers are locked by the account";
+pub const ERR_NOTHING_TO_SEIZE: &str = "Account has no balance to seize";
+pub const ERR_RECEIVER_NOT_ALLOWED: &str = "Receiver is not in the allowed receivers of the sender";
+pub const ERR_TOO_MANY_ALLOWED_RECEIVERS: &str = "Too many allowed receivers";
+pub const ERR_EXCEEDS_MAX_HOLDING: &str = "Exceeds max holding";
+pub const ERR_BELOW_MIN_REMAINING_BALANCE: &str =
+    "Transfer would leave less than the min remaining balance";
+pub const ERR_BALANCE_LOCKED: &str = "Transfer exceeds the unlocked balance";
+pub const ERR_LOCK_ABOVE_BALANCE: &str = "Can't lock more than the balance";
+
+pub const ERR_SELF_APPROVAL: &str = "Owner can't approve itself";
+pub const ERR_INSUFFICIENT_ALLOWANCE: &str = "Insufficient allowance";
+
+pub const ERR_NOT_ED25519_KEY: &str = "Only ed25519 keys are supported";
+pub const ERR_UNKNOWN_PERMIT_KEY: &str = "Public key is not the permit key of the owner";
+pub const ERR_INVALID_NONCE: &str = "Invalid permit nonce";
+pub const ERR_INVALID_SIGNATURE: &str = "Invalid signature";
+pub const ERR_PERMIT_EXPIRED: &str = "Permit deadline passed";
+
+pub const ERR_REQUIRES_DEPOSIT: &str = "Requires attached deposit of at least 1 yoctoNEAR";
+pub const ERR_STORAGE_BELOW_BASE: &str =
+    "Account storage can't be below the base requirement of the token";
+pub const ERR_STORAGE_TOPUP_REQUIRED: &str =
+    "The account storage deposit is below the current bound, call require_storage_topup";
+pub const ERR_STORAGE_OVERPAYMENT: &str =
+    "The attached deposit is more than twice the minimum storage balance";
+pub const ERR_NO_INACTIVITY_THRESHOLD: &str = "Inactivity threshold is not set";
+pub const ERR_RECENTLY_ACTIVE: &str = "Account was active within the inactivity threshold";
+pub const ERR_RECLAIM_POSITIVE_BALANCE: &str = "Only accounts with a zero balance can be reclaimed";
+pub const ERR_INSUFFICIENT_STORAGE_DEPOSIT: &str =
+    "The attached deposit is less than the minimum storage balance";
+
+pub const ERR_VESTING_DURATION: &str = "Vesting duration must be positive";
+pub const ERR_CLIFF_BEFORE_START: &str = "Cliff can't be before the start";
+pub const ERR_NO_VESTING: &str = "No vesting schedules";
+pub const ERR_NOTHING_TO_CLAIM: &str = "Nothing to claim";
+pub const ERR_NO_CLAIM_WINDOW: &str = "Claim window is not set";
+
+pub const ERR_MALFORMED_MSG: &str = "Malformed transfer call msg";
+pub const ERR_INVALID_MAX_REFUND: &str = "Invalid max_refund in transfer call msg";
+pub const ERR_MAX_REFUND_TOO_HIGH: &str = "max_refund can't exceed the transferred amount";
+pub const ERR_TRANSFER_CALL_RECEIVER_NOT_APPROVED: &str =
+    "Receiver is not an approved transfer call receiver";
+pub const ERR_MORE_GAS_REQUIRED: &str = "More gas is required";
+pub const ERR_RESOLVE_GAS_OUT_OF_RANGE: &str = "Resolve gas is out of the allowed range";
+pub const ERR_UNREGISTER_POSITIVE_BALANCE: &str =
+    "Can't unregister the account with the positive balance without force";
+pub const ERR_UNCLAIMED_VESTING: &str = "Can't unregister the account with unclaimed vesting";
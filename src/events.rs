@@ -0,0 +1,30 @@
+use near_sdk::serde::Serialize;
+use near_sdk::{env, serde_json};
+
+/// Standard name of the NEP-297 events specific to this token. Transfers, mints and burns are
+/// reported with the standard `nep141` events instead.
+pub const EVENT_STANDARD: &str = "publicai";
+pub const EVENT_VERSION: &str = "1.0.0";
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T: Serialize> {
+    standard: &'a str,
+    version: &'a str,
+    event: &'a str,
+    data: [T; 1],
+}
+
+/// Logs a NEP-297 `event` carrying `data` as its single data entry.
+pub fn emit_event<T: Serialize>(event: &str, data: T) {
+    let log = EventLog {
+        standard: EVENT_STANDARD,
+        version: EVENT_VERSION,
+        event,
+        data: [data],
+    };
+    env::log_str(&format!(
+        "EVENT_JSON:{}",
+        serde_json::to_string(&log).unwrap_or_else(|_| env::abort())
+    ));
+}
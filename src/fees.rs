@@ -0,0 +1,719 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, log, near, require, AccountId};
+
+use crate::errors::{ERR_BURN_TOO_HIGH, ERR_FEE_TOO_HIGH, ERR_REWARD_TOO_HIGH};
+use crate::math::checked_sub_balance;
+use crate::supply::paginate_accounts;
+use crate::{Contract, ContractExt};
+
+/// Denominator of basis point values.
+pub const BPS_DENOMINATOR: u128 = 10_000;
+/// Upper bound of the transfer fee, 10%. The transfer burn and reward have the same bound.
+pub const MAX_TRANSFER_FEE_BPS: u16 = 1_000;
+
+/// Rounding of the transfer fee computed from basis points.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FeeRounding {
+    #[default]
+    Down,
+    Up,
+    Nearest,
+}
+
+/// What happens to the collected transfer fees.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FeeMode {
+    /// Fees are credited to the fee collector.
+    #[default]
+    Collect,
+    /// Fees are burned, removing them from the total supply.
+    Burn,
+}
+
+/// How a transferred amount is split, returned by `net_transfer_amount`.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub struct NetBreakdown {
+    pub to_receiver: U128,
+    pub fee: U128,
+    pub burned: U128,
+    pub to_reward_pool: U128,
+}
+
+/// How a transferred amount is split between its destinations, computed by
+/// `compute_splits`. The parts always add up to the transferred amount.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Splits {
+    pub to_receiver: Balance,
+    /// The transfer fee, burned instead of collected in `FeeMode::Burn`.
+    pub to_collector: Balance,
+    pub burned: Balance,
+    pub to_reward_pool: Balance,
+}
+
+#[near]
+impl Contract {
+    /// Sets the fee charged on transfers and transfer calls, in basis points of the
+    /// transferred amount.
+    #[payable]
+    pub fn set_transfer_fee(&mut self, fee_bps: u16) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(fee_bps <= MAX_TRANSFER_FEE_BPS, ERR_FEE_TOO_HIGH);
+        self.transfer_fee_bps = fee_bps;
+    }
+
+    pub fn get_transfer_fee(&self) -> u16 {
+        self.transfer_fee_bps
+    }
+
+    /// Sets how fractional fees are rounded. Rounding down lets senders avoid fees by
+    /// splitting transfers into tiny amounts.
+    #[payable]
+    pub fn set_fee_rounding(&mut self, rounding: FeeRounding) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.fee_rounding = rounding;
+    }
+
+    pub fn get_fee_rounding(&self) -> FeeRounding {
+        self.fee_rounding
+    }
+
+    /// Sets whether transfer fees are credited to the fee collector or burned.
+    #[payable]
+    pub fn set_fee_mode(&mut self, mode: FeeMode) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.fee_mode = mode;
+    }
+
+    pub fn get_fee_mode(&self) -> FeeMode {
+        self.fee_mode
+    }
+
+    /// Sets the share of every `ft_transfer` burned, in basis points of the transferred amount.
+    /// The burn is charged on top of the fee and follows the same exemptions.
+    #[payable]
+    pub fn set_transfer_burn(&mut self, burn_bps: u16) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(burn_bps <= MAX_TRANSFER_FEE_BPS, ERR_BURN_TOO_HIGH);
+        self.transfer_burn_bps = burn_bps;
+    }
+
+    pub fn get_transfer_burn(&self) -> u16 {
+        self.transfer_burn_bps
+    }
+
+    /// Sets the share of every `ft_transfer` sent to the reward pool, in basis points of the
+    /// transferred amount. It is charged on top of the fee and the burn, follows the same
+    /// exemptions, and only while a reward pool is set.
+    #[payable]
+    pub fn set_transfer_reward(&mut self, reward_bps: u16) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(reward_bps <= MAX_TRANSFER_FEE_BPS, ERR_REWARD_TOO_HIGH);
+        self.transfer_reward_bps = reward_bps;
+    }
+
+    pub fn get_transfer_reward(&self) -> u16 {
+        self.transfer_reward_bps
+    }
+
+    /// Sets the account receiving the reward pool share of transfers, or stops the
+    /// contribution with `None`. It must be registered.
+    #[payable]
+    pub fn set_reward_pool(&mut self, reward_pool_id: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(reward_pool_id) = &reward_pool_id {
+            self.assert_valid_external_account(reward_pool_id);
+            require!(
+                self.token.accounts.contains_key(reward_pool_id),
+                format!("The account {} is not registered", reward_pool_id)
+            );
+        }
+        log!("Reward pool set to {:?}", reward_pool_id);
+        self.reward_pool_id = reward_pool_id;
+    }
+
+    pub fn get_reward_pool(&self) -> Option<AccountId> {
+        self.reward_pool_id.clone()
+    }
+
+    /// Previews how `amount` sent between two non-exempt accounts is split between the
+    /// receiver, the fee, the burn and the reward pool under the current settings.
+    pub fn net_transfer_amount(&self, amount: U128) -> NetBreakdown {
+        let reward_bps = if self.reward_pool_id.is_some() {
+            self.transfer_reward_bps
+        } else {
+            0
+        };
+        let splits = split_amount(
+            amount.0,
+            self.transfer_fee_bps,
+            self.fee_rounding,
+            self.transfer_burn_bps,
+            reward_bps,
+        );
+        NetBreakdown {
+            to_receiver: splits.to_receiver.into(),
+            fee: splits.to_collector.into(),
+            burned: splits.burned.into(),
+            to_reward_pool: splits.to_reward_pool.into(),
+        }
+    }
+
+    /// Exempts `account_id` from transfer fees, both as a sender and as a receiver.
+    #[payable]
+    pub fn add_fee_exempt(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.fee_exempt.insert(&account_id)
+    }
+
+    #[payable]
+    pub fn remove_fee_exempt(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.fee_exempt.remove(&account_id)
+    }
+
+    pub fn is_fee_exempt(&self, account_id: AccountId) -> bool {
+        self.fee_exempt.contains(&account_id)
+    }
+
+    pub fn get_fee_exempt_accounts(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        paginate_accounts(&self.fee_exempt, from_index, limit)
+    }
+}
+
+impl Contract {
+    /// Account collecting transfer fees.
+    pub(crate) fn fee_collector(&self) -> &AccountId {
+        &self.treasury_id
+    }
+
+    /// Splits `amount` moved from `sender_id` to `receiver_id` between the receiver, the fee
+    /// collector, the burn and the reward pool. Nothing is charged when either side is exempt
+    /// or when tokens are sent to the burn address. The fee isn't charged when the collector
+    /// is either side, and the reward contribution when the reward pool is, as they would
+    /// only pay themselves.
+    pub(crate) fn compute_splits(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Splits {
+        if self.burn_address.as_ref() == Some(receiver_id)
+            || self.fee_exempt.contains(sender_id)
+            || self.fee_exempt.contains(receiver_id)
+        {
+            return split_amount(amount, 0, self.fee_rounding, 0, 0);
+        }
+        let fee_bps = if sender_id == self.fee_collector() || receiver_id == self.fee_collector() {
+            0
+        } else {
+            self.transfer_fee_bps
+        };
+        let reward_bps = match &self.reward_pool_id {
+            Some(reward_pool_id)
+                if reward_pool_id != sender_id && reward_pool_id != receiver_id =>
+            {
+                self.transfer_reward_bps
+            }
+            _ => 0,
+        };
+        split_amount(
+            amount,
+            fee_bps,
+            self.fee_rounding,
+            self.transfer_burn_bps,
+            reward_bps,
+        )
+    }
+}
+
+/// Splits `amount` with the given rates. The fee follows `fee_rounding`, the burn and the
+/// reward contribution round down, and the receiver gets the remainder, so the parts always
+/// add up to `amount`.
+pub(crate) fn split_amount(
+    amount: Balance,
+    fee_bps: u16,
+    fee_rounding: FeeRounding,
+    burn_bps: u16,
+    reward_bps: u16,
+) -> Splits {
+    let to_collector = bps_of(amount, fee_bps, fee_rounding);
+    let burned = bps_of(amount, burn_bps, FeeRounding::Down);
+    let to_reward_pool = bps_of(amount, reward_bps, FeeRounding::Down);
+    let to_receiver = checked_sub_balance(amount, to_collector + burned + to_reward_pool);
+    Splits {
+        to_receiver,
+        to_collector,
+        burned,
+        to_reward_pool,
+    }
+}
+
+/// Returns `bps` basis points of `amount`, for `bps` up to `BPS_DENOMINATOR`. The amount is
+/// split around the denominator so that no intermediate product overflows, whatever the
+/// balance.
+pub(crate) fn bps_of(amount: Balance, bps: u16, rounding: FeeRounding) -> Balance {
+    let bps = u128::from(bps);
+    let whole = amount / BPS_DENOMINATOR * bps;
+    let scaled_rest = amount % BPS_DENOMINATOR * bps;
+    whole
+        + match rounding {
+            FeeRounding::Down => scaled_rest / BPS_DENOMINATOR,
+            FeeRounding::Up => scaled_rest.div_ceil(BPS_DENOMINATOR),
+            FeeRounding::Nearest => (scaled_rest + BPS_DENOMINATOR / 2) / BPS_DENOMINATOR,
+        }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Sets a 1% fee and funds `user1` with 10_000 tokens.
+    fn setup_with_fee() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 10_000.into(), None);
+        contract.set_transfer_fee(100);
+
+        (contract, context)
+    }
+
+    fn transfer(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: Balance,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(sender_id)
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(receiver_id, amount.into(), None);
+    }
+
+    fn exempt(contract: &mut Contract, context: &mut VMContextBuilder, account_id: AccountId) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert!(contract.add_fee_exempt(account_id));
+    }
+
+    #[test]
+    fn test_transfer_charges_fee() {
+        let (mut contract, mut context) = setup_with_fee();
+        let owner_balance = contract.ft_balance_of(owner()).0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 9_000);
+        assert_eq!(contract.ft_balance_of(user2()).0, 990);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance + 10);
+    }
+
+    #[test]
+    fn test_transfer_to_fee_collector_charges_no_fee() {
+        let (mut contract, mut context) = setup_with_fee();
+        let owner_balance = contract.ft_balance_of(owner()).0;
+
+        transfer(&mut contract, &mut context, user1(), owner(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 9_000);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance + 1_000);
+        assert!(!get_logs()
+            .iter()
+            .any(|log| log.contains(r#""memo":"transfer fee""#)));
+    }
+
+    #[test]
+    fn test_transfer_from_exempt_sender() {
+        let (mut contract, mut context) = setup_with_fee();
+        exempt(&mut contract, &mut context, user1());
+        let owner_balance = contract.ft_balance_of(owner()).0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance);
+    }
+
+    #[test]
+    fn test_transfer_to_exempt_receiver() {
+        let (mut contract, mut context) = setup_with_fee();
+        exempt(&mut contract, &mut context, user2());
+        let owner_balance = contract.ft_balance_of(owner()).0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance);
+    }
+
+    #[test]
+    fn test_remove_fee_exempt() {
+        let (mut contract, mut context) = setup_with_fee();
+        exempt(&mut contract, &mut context, user1());
+        assert!(contract.is_fee_exempt(user1()));
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert!(contract.remove_fee_exempt(user1()));
+        assert!(!contract.is_fee_exempt(user1()));
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+        assert_eq!(contract.ft_balance_of(user2()).0, 990);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_add_fee_exempt_only_owner() {
+        let (mut contract, mut context) = setup_with_fee();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.add_fee_exempt(user1());
+    }
+
+    fn set_transfer_burn(contract: &mut Contract, context: &mut VMContextBuilder, burn_bps: u16) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_burn(burn_bps);
+    }
+
+    #[test]
+    fn test_transfer_burns_share() {
+        let (mut contract, mut context) = setup_with_fee();
+        set_transfer_burn(&mut contract, &mut context, 50);
+        let owner_balance = contract.ft_balance_of(owner()).0;
+        let total_supply = contract.ft_total_supply().0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 985);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance + 10);
+        assert_eq!(contract.ft_total_supply().0, total_supply - 5);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"ft_burn""#)));
+    }
+
+    #[test]
+    fn test_fee_collected_by_default() {
+        let (mut contract, mut context) = setup_with_fee();
+        assert_eq!(contract.get_fee_mode(), FeeMode::Collect);
+        let owner_balance = contract.ft_balance_of(owner()).0;
+        let total_supply = contract.ft_total_supply().0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance + 10);
+        assert_eq!(contract.ft_total_supply().0, total_supply);
+        assert!(!get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"ft_burn""#)));
+    }
+
+    #[test]
+    fn test_fee_burned_in_burn_mode() {
+        let (mut contract, mut context) = setup_with_fee();
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_fee_mode(FeeMode::Burn);
+        let owner_balance = contract.ft_balance_of(owner()).0;
+        let total_supply = contract.ft_total_supply().0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 9_000);
+        assert_eq!(contract.ft_balance_of(user2()).0, 990);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance);
+        assert_eq!(contract.ft_total_supply().0, total_supply - 10);
+        let logs = get_logs();
+        assert!(logs
+            .iter()
+            .any(|log| log.contains(r#""event":"ft_burn""#) && log.contains(r#""amount":"10""#)));
+        assert!(!logs
+            .iter()
+            .any(|log| log.contains(r#""memo":"transfer fee""#)
+                && log.contains(r#""event":"ft_transfer""#)));
+    }
+
+    #[test]
+    fn test_net_transfer_amount_fee_only() {
+        let (contract, _) = setup_with_fee();
+
+        assert_eq!(
+            contract.net_transfer_amount(1_000.into()),
+            NetBreakdown {
+                to_receiver: 990.into(),
+                fee: 10.into(),
+                burned: 0.into(),
+                to_reward_pool: 0.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_net_transfer_amount_burn_only() {
+        let (mut contract, mut context) = setup();
+        set_transfer_burn(&mut contract, &mut context, 50);
+
+        assert_eq!(
+            contract.net_transfer_amount(1_000.into()),
+            NetBreakdown {
+                to_receiver: 995.into(),
+                fee: 0.into(),
+                burned: 5.into(),
+                to_reward_pool: 0.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_net_transfer_amount_fee_and_burn() {
+        let (mut contract, mut context) = setup_with_fee();
+        set_transfer_burn(&mut contract, &mut context, 50);
+
+        assert_eq!(
+            contract.net_transfer_amount(1_000.into()),
+            NetBreakdown {
+                to_receiver: 985.into(),
+                fee: 10.into(),
+                burned: 5.into(),
+                to_reward_pool: 0.into(),
+            }
+        );
+        // previewing doesn't change balances
+        assert_eq!(contract.ft_balance_of(user1()).0, 10_000);
+    }
+
+    fn set_fee_rounding(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        rounding: FeeRounding,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_fee_rounding(rounding);
+    }
+
+    #[test]
+    fn test_fee_rounding_down_by_default() {
+        let (mut contract, mut context) = setup_with_fee();
+        assert_eq!(contract.get_fee_rounding(), FeeRounding::Down);
+
+        // 1% of 199 is 1.99
+        transfer(&mut contract, &mut context, user1(), user2(), 199);
+        assert_eq!(contract.ft_balance_of(user2()).0, 198);
+    }
+
+    #[test]
+    fn test_fee_rounding_up() {
+        let (mut contract, mut context) = setup_with_fee();
+        set_fee_rounding(&mut contract, &mut context, FeeRounding::Up);
+
+        // 1% of 101 is 1.01
+        transfer(&mut contract, &mut context, user1(), user2(), 101);
+        assert_eq!(contract.ft_balance_of(user2()).0, 99);
+        assert_eq!(contract.net_transfer_amount(1.into()).fee.0, 1);
+    }
+
+    #[test]
+    fn test_fee_rounding_nearest() {
+        let (mut contract, mut context) = setup_with_fee();
+        set_fee_rounding(&mut contract, &mut context, FeeRounding::Nearest);
+
+        assert_eq!(contract.net_transfer_amount(149.into()).fee.0, 1);
+        assert_eq!(contract.net_transfer_amount(150.into()).fee.0, 2);
+        transfer(&mut contract, &mut context, user1(), user2(), 249);
+        assert_eq!(contract.ft_balance_of(user2()).0, 247);
+    }
+
+    fn reward_pool() -> AccountId {
+        accounts(4)
+    }
+
+    /// Sets a 1% fee, a 0.5% burn and a 0.3% reward contribution to a registered pool.
+    fn setup_with_reward_pool() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup_with_fee();
+        register_user(&mut contract, &mut context, reward_pool());
+        set_transfer_burn(&mut contract, &mut context, 50);
+        contract.set_transfer_reward(30);
+        contract.set_reward_pool(Some(reward_pool()));
+
+        (contract, context)
+    }
+
+    #[test]
+    fn test_split_amount_parts_add_up() {
+        let roundings = [FeeRounding::Down, FeeRounding::Up, FeeRounding::Nearest];
+        for rounding in roundings {
+            for amount in (0..2_000).chain([999_999, 1_000_001, u64::MAX as Balance]) {
+                let splits = split_amount(amount, 137, rounding, 53, 29);
+                assert_eq!(
+                    splits.to_receiver
+                        + splits.to_collector
+                        + splits.burned
+                        + splits.to_reward_pool,
+                    amount
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_amount_uneven() {
+        // 1.37% of 999 is 13.68, 0.53% is 5.29 and 0.29% is 2.89
+        assert_eq!(
+            split_amount(999, 137, FeeRounding::Up, 53, 29),
+            Splits {
+                to_receiver: 978,
+                to_collector: 14,
+                burned: 5,
+                to_reward_pool: 2,
+            }
+        );
+        assert_eq!(
+            split_amount(999, 137, FeeRounding::Down, 53, 29).to_receiver,
+            979
+        );
+    }
+
+    #[test]
+    fn test_transfer_contributes_to_reward_pool() {
+        let (mut contract, mut context) = setup_with_reward_pool();
+        let owner_balance = contract.ft_balance_of(owner()).0;
+        let total_supply = contract.ft_total_supply().0;
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_999);
+
+        // 1% of 1_999 is 19.99, 0.5% is 9.995 and 0.3% is 5.997
+        assert_eq!(contract.ft_balance_of(user1()).0, 10_000 - 1_999);
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_999 - 19 - 9 - 5);
+        assert_eq!(contract.ft_balance_of(owner()).0, owner_balance + 19);
+        assert_eq!(contract.ft_balance_of(reward_pool()).0, 5);
+        assert_eq!(contract.ft_total_supply().0, total_supply - 9);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""memo":"reward pool contribution""#)));
+        assert_eq!(
+            contract.net_transfer_amount(1_999.into()),
+            NetBreakdown {
+                to_receiver: 1_966.into(),
+                fee: 19.into(),
+                burned: 9.into(),
+                to_reward_pool: 5.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_no_reward_contribution_without_pool() {
+        let (mut contract, mut context) = setup_with_reward_pool();
+        contract.set_reward_pool(None);
+
+        transfer(&mut contract, &mut context, user1(), user2(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 985);
+        assert_eq!(contract.ft_balance_of(reward_pool()).0, 0);
+    }
+
+    #[test]
+    fn test_transfer_to_reward_pool_contributes_nothing() {
+        let (mut contract, mut context) = setup_with_reward_pool();
+
+        transfer(&mut contract, &mut context, user1(), reward_pool(), 1_000);
+
+        assert_eq!(contract.ft_balance_of(reward_pool()).0, 985);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_set_reward_pool_unregistered() {
+        let (mut contract, mut context) = setup_with_fee();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_reward_pool(Some(reward_pool()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer reward is too high")]
+    fn test_set_transfer_reward_above_max() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_reward(MAX_TRANSFER_FEE_BPS + 1);
+    }
+
+    #[test]
+    fn test_bps_of_max_amount() {
+        // u128::MAX ends with a 5, so a tenth of it has a fractional half
+        assert_eq!(bps_of(u128::MAX, 1_000, FeeRounding::Down), u128::MAX / 10);
+        assert_eq!(
+            bps_of(u128::MAX, 1_000, FeeRounding::Up),
+            u128::MAX / 10 + 1
+        );
+        assert_eq!(
+            bps_of(u128::MAX, 1_000, FeeRounding::Nearest),
+            u128::MAX / 10 + 1
+        );
+        assert_eq!(bps_of(u128::MAX, 10_000, FeeRounding::Down), u128::MAX);
+        assert_eq!(bps_of(199, 100, FeeRounding::Down), 1);
+        assert_eq!(bps_of(101, 100, FeeRounding::Up), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer fee is too high")]
+    fn test_set_transfer_fee_above_max() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_fee(MAX_TRANSFER_FEE_BPS + 1);
+    }
+}
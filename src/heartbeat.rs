@@ -0,0 +1,72 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, env, near};
+
+use crate::events::emit_event;
+use crate::{Contract, ContractExt};
+
+/// Data of the `heartbeat` event.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct Heartbeat {
+    block_height: U64,
+    timestamp_ns: U64,
+}
+
+#[near]
+impl Contract {
+    /// Emits a `heartbeat` event with the current block height and timestamp, so off-chain
+    /// monitoring can alert when the operators stop seeing it.
+    #[payable]
+    pub fn heartbeat(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        emit_event(
+            "heartbeat",
+            Heartbeat {
+                block_height: env::block_height().into(),
+                timestamp_ns: env::block_timestamp().into(),
+            },
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::get_logs;
+    use near_sdk::{testing_env, NearToken};
+
+    use crate::tests::{owner, setup, user1};
+
+    #[test]
+    fn test_heartbeat_emits_event() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_height(42)
+            .block_timestamp(1_700_000_000_000_000_000)
+            .build());
+        contract.heartbeat();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"publicai","version":"1.0.0","event":"heartbeat","data":[{"block_height":"42","timestamp_ns":"1700000000000000000"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_heartbeat_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.heartbeat();
+    }
+}
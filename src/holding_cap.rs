@@ -0,0 +1,173 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::ERR_EXCEEDS_MAX_HOLDING;
+use crate::supply::paginate_accounts;
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Caps the balance any single account can reach through transfers and mints, e.g. for a
+    /// fair launch, or removes the cap with `None`. Balances already above the cap are kept.
+    #[payable]
+    pub fn set_max_balance_per_account(&mut self, max_balance: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Max balance per account set to {:?}", max_balance);
+        self.max_balance_per_account = max_balance.map(|max_balance| max_balance.0);
+    }
+
+    pub fn get_max_balance_per_account(&self) -> Option<U128> {
+        self.max_balance_per_account.map(U128)
+    }
+
+    /// Exempts `account_id` (exchange, liquidity pool...) from the max balance per account.
+    /// The owner, the treasury, the burn address and the contract account are always exempt.
+    #[payable]
+    pub fn add_holding_cap_exempt(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.holding_cap_exempt.insert(&account_id)
+    }
+
+    #[payable]
+    pub fn remove_holding_cap_exempt(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.holding_cap_exempt.remove(&account_id)
+    }
+
+    pub fn is_holding_cap_exempt(&self, account_id: AccountId) -> bool {
+        self.holding_cap_exempt.contains(&account_id)
+    }
+
+    pub fn get_holding_cap_exempt_accounts(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        paginate_accounts(&self.holding_cap_exempt, from_index, limit)
+    }
+}
+
+impl Contract {
+    /// Whether crediting `amount` to `account_id` would push it above the max balance per
+    /// account.
+    pub(crate) fn exceeds_max_holding(&self, account_id: &AccountId, amount: Balance) -> bool {
+        let Some(max_balance) = self.max_balance_per_account else {
+            return false;
+        };
+        if *account_id == self.owner_id
+            || *account_id == self.treasury_id
+            || *account_id == env::current_account_id()
+            || self.burn_address.as_ref() == Some(account_id)
+            || self.holding_cap_exempt.contains(account_id)
+        {
+            return false;
+        }
+        self.token
+            .accounts
+            .get(account_id)
+            .unwrap_or(0)
+            .checked_add(amount)
+            .is_none_or(|balance| balance > max_balance)
+    }
+
+    pub(crate) fn assert_within_max_holding(&self, account_id: &AccountId, amount: Balance) {
+        require!(
+            !self.exceeds_max_holding(account_id, amount),
+            ERR_EXCEEDS_MAX_HOLDING
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Caps balances at 1_000, user1 and user2 are registered.
+    fn setup_with_cap() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_max_balance_per_account(Some(1_000.into()));
+        (contract, context)
+    }
+
+    #[test]
+    fn test_transfer_under_max_holding() {
+        let (mut contract, _) = setup_with_cap();
+
+        contract.ft_transfer(user1(), 600.into(), None);
+        contract.ft_transfer(user1(), 400.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Exceeds max holding")]
+    fn test_transfer_above_max_holding() {
+        let (mut contract, _) = setup_with_cap();
+
+        contract.ft_transfer(user1(), 600.into(), None);
+        contract.ft_transfer(user1(), 401.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Exceeds max holding")]
+    fn test_mint_above_max_holding() {
+        let (mut contract, _) = setup_with_cap();
+
+        contract.add_minter(owner());
+        contract.mint(user1(), 1_001.into(), None);
+    }
+
+    #[test]
+    fn test_max_holding_exemptions() {
+        let (mut contract, mut context) = setup_with_cap();
+
+        // the owner keeps the whole supply
+        contract.add_holding_cap_exempt(user2());
+        contract.ft_transfer(user2(), 5_000.into(), None);
+        assert_eq!(contract.ft_balance_of(user2()).0, 5_000);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(owner(), 4_000.into(), None);
+        assert_eq!(
+            contract.get_holding_cap_exempt_accounts(None, None),
+            vec![user2()]
+        );
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_max_balance_per_account(None);
+        contract.ft_transfer(user1(), 5_000.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 5_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_set_max_balance_per_account_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_max_balance_per_account(Some(1_000.into()));
+    }
+}
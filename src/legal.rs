@@ -0,0 +1,92 @@
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{assert_one_yocto, log, near, require};
+
+use crate::errors::ERR_INVALID_LEGAL_REFERENCE_HASH;
+use crate::{Contract, ContractExt};
+
+/// Link to the legal documents of the token (terms of service...), kept apart from the
+/// branding `reference` of the standard metadata.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegalReference {
+    pub reference: String,
+    /// sha256 of the document at `reference`.
+    pub reference_hash: Base64VecU8,
+}
+
+#[near]
+impl Contract {
+    /// Sets the legal document reference, or removes it with `None`.
+    #[payable]
+    pub fn set_legal_reference(&mut self, legal_reference: Option<LegalReference>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(legal_reference) = &legal_reference {
+            require!(
+                legal_reference.reference_hash.0.len() == 32,
+                ERR_INVALID_LEGAL_REFERENCE_HASH
+            );
+        }
+        log!("Legal reference set to {:?}", legal_reference);
+        self.legal_reference = legal_reference;
+    }
+
+    pub fn get_legal_reference(&self) -> Option<LegalReference> {
+        self.legal_reference.clone()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::metadata::FungibleTokenMetadataProvider;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, setup};
+
+    fn set_legal_reference(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        legal_reference: Option<LegalReference>,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_legal_reference(legal_reference);
+    }
+
+    #[test]
+    fn test_set_legal_reference() {
+        let (mut contract, mut context) = setup();
+        assert_eq!(contract.get_legal_reference(), None);
+
+        let legal_reference = LegalReference {
+            reference: "https://example.com/terms.pdf".to_string(),
+            reference_hash: vec![7; 32].into(),
+        };
+        set_legal_reference(&mut contract, &mut context, Some(legal_reference.clone()));
+        assert_eq!(contract.get_legal_reference(), Some(legal_reference));
+        // the standard metadata is untouched
+        assert_eq!(contract.ft_metadata().reference, None);
+
+        set_legal_reference(&mut contract, &mut context, None);
+        assert_eq!(contract.get_legal_reference(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Legal reference hash must be 32 bytes")]
+    fn test_set_legal_reference_invalid_hash() {
+        let (mut contract, mut context) = setup();
+
+        set_legal_reference(
+            &mut contract,
+            &mut context,
+            Some(LegalReference {
+                reference: "https://example.com/terms.pdf".to_string(),
+                reference_hash: vec![7; 20].into(),
+            }),
+        );
+    }
+}
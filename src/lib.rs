@@ -1,49 +1,322 @@
+use near_contract_standards::fungible_token::events::{FtBurn, FtTransfer};
 use near_contract_standards::fungible_token::metadata::{
-    FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
+    FungibleTokenMetadata, FungibleTokenMetadataProvider,
 };
 use near_contract_standards::fungible_token::{
-    FungibleToken, FungibleTokenCore, FungibleTokenResolver,
+    Balance, FungibleToken, FungibleTokenCore, FungibleTokenResolver,
 };
 use near_contract_standards::storage_management::{
     StorageBalance, StorageBalanceBounds, StorageManagement,
 };
 use near_sdk::borsh::BorshSerialize;
-use near_sdk::collections::LazyOption;
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
 use near_sdk::{
-    assert_one_yocto, env, log, near, require, AccountId, BorshStorageKey, NearToken,
-    PanicOnDefault, PromiseOrValue,
+    assert_one_yocto, env, log, near, require, AccountId, BorshStorageKey, Gas, NearToken,
+    PanicOnDefault, PromiseOrValue, PublicKey, StorageUsage,
 };
 
+use crate::errors::{
+    ERR_ALREADY_INITIALIZED, ERR_DEADLINE_PASSED, ERR_DECIMALS_CHANGED, ERR_EMPTY_OWNER,
+    ERR_FINALIZED, ERR_INVALID_SPEC, ERR_METADATA_COOLDOWN, ERR_METADATA_LOCKED,
+    ERR_METADATA_NOT_SET, ERR_NOT_OWNER, ERR_NO_REWARD_POOL, ERR_SELF_TRANSFER, ERR_ZERO_AMOUNT,
+};
+use crate::fees::{FeeMode, FeeRounding, Splits};
+use crate::legal::LegalReference;
+use crate::locks::BalanceLock;
+use crate::rebase::RebaseProgress;
+use crate::receiver_policy::{HeldTransfer, UnregisteredReceiverPolicy};
+use crate::scheduled_metadata::ScheduledMetadata;
+use crate::vesting::VestingSchedule;
+
+mod account_ids;
+mod activity;
+mod allowances;
+mod allowed_receivers;
+mod batch;
+mod burn;
+mod compliance;
+mod describe;
+mod display;
+mod distribution;
+mod errors;
+mod events;
+mod fees;
+mod heartbeat;
+mod holding_cap;
+mod legal;
+mod locks;
+mod math;
+mod memos;
+mod migration;
+mod min_remaining;
+mod mint;
+mod observer;
+mod pause;
+mod permit;
+mod precision;
+mod preflight;
+mod rebase;
+mod receiver_policy;
+mod rich_events;
+mod scheduled_metadata;
+mod storage;
+mod supply;
+mod supply_history;
+mod transfer_call;
+mod treasury;
+mod vesting;
+mod volume;
+
 #[derive(PanicOnDefault)]
 #[near(contract_state)]
 pub struct Contract {
     owner_id: AccountId,
     token: FungibleToken,
+    base_account_storage_usage: StorageUsage,
+    storage_covered: LookupMap<AccountId, StorageUsage>,
     metadata: LazyOption<FungibleTokenMetadata>,
+    deployed_at_ns: u64,
+    metadata_change_cooldown_ns: u64,
+    last_metadata_change_ns: Option<u64>,
+    scheduled_metadata: Option<ScheduledMetadata>,
+    metadata_locked: bool,
+    legal_reference: Option<LegalReference>,
+    vesting: LookupMap<AccountId, Vector<VestingSchedule>>,
+    transfer_fee_bps: u16,
+    transfer_burn_bps: u16,
+    transfer_reward_bps: u16,
+    reward_pool_id: Option<AccountId>,
+    transfer_precision: Option<u8>,
+    fee_rounding: FeeRounding,
+    fee_mode: FeeMode,
+    fee_exempt: UnorderedSet<AccountId>,
+    finalized: bool,
+    memo_log_enabled: bool,
+    rich_events: bool,
+    recent_memos: LookupMap<AccountId, Vec<String>>,
+    minters: UnorderedSet<AccountId>,
+    excluded_accounts: UnorderedSet<AccountId>,
+    burn_address: Option<AccountId>,
+    holders: UnorderedSet<AccountId>,
+    nonzero_holders: u64,
+    distribution: Vec<(Balance, u64)>,
+    rebase_progress: Option<RebaseProgress>,
+    transfer_observer: Option<AccountId>,
+    max_supply: Option<Balance>,
+    max_balance_per_account: Option<Balance>,
+    min_remaining_balance: Option<Balance>,
+    holding_cap_exempt: UnorderedSet<AccountId>,
+    supply_warning_bps: Option<u16>,
+    supply_history: Vector<(u64, Balance)>,
+    supply_checkpoints: u64,
+    total_minted: Balance,
+    total_burned: Balance,
+    allowances: LookupMap<(AccountId, AccountId), Balance>,
+    allowance_expiries: LookupMap<(AccountId, AccountId), u64>,
+    allowance_spenders: LookupMap<AccountId, Vec<AccountId>>,
+    mint_unlock_ns: Option<u64>,
+    frozen_accounts: UnorderedSet<AccountId>,
+    balance_locks: LookupMap<AccountId, BalanceLock>,
+    self_locks: LookupMap<AccountId, bool>,
+    allowed_receivers: LookupMap<AccountId, Vec<AccountId>>,
+    paused: bool,
+    minting_paused: bool,
+    pause_reason: Option<String>,
+    treasury_id: AccountId,
+    vesting_escrowed: Balance,
+    storage_initialized: bool,
+    reject_storage_overpayment: bool,
+    migration_mode: bool,
+    permit_keys: LookupMap<AccountId, PublicKey>,
+    permit_nonces: LookupMap<AccountId, u64>,
+    resolve_gas: Gas,
+    allow_zero_transfers: bool,
+    burn_unresolved_refunds: bool,
+    restrict_transfer_call: bool,
+    transfer_call_receivers: UnorderedSet<AccountId>,
+    activity_tracking_enabled: bool,
+    last_active: LookupMap<AccountId, u64>,
+    last_active_epoch: LookupMap<AccountId, u64>,
+    active_accounts_epoch_height: u64,
+    active_accounts_count: u64,
+    inactivity_threshold_ns: Option<u64>,
+    unregistered_receiver_policy: UnregisteredReceiverPolicy,
+    pending_claims: LookupMap<AccountId, Vec<HeldTransfer>>,
+    claim_window_ns: Option<u64>,
+    pending_claims_escrowed: Balance,
+    max_batch_size: u32,
+    volume_cap_per_epoch: Option<Balance>,
+    volume_epoch_height: u64,
+    epoch_volume: Balance,
+}
+
+/// Snapshot of the contract settings, returned by `get_config`.
+#[near(serializers = [json])]
+pub struct ContractConfig {
+    pub owner_id: AccountId,
+    pub treasury_id: AccountId,
+    pub transfer_fee_bps: u16,
+    pub finalized: bool,
+}
+
+/// Every configurable numeric limit of the contract, returned by `get_limits`. Disabled
+/// limits are `None`.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub struct Limits {
+    pub transfer_fee_bps: u16,
+    pub transfer_burn_bps: u16,
+    pub transfer_reward_bps: u16,
+    pub transfer_precision: Option<u8>,
+    pub max_supply: Option<U128>,
+    pub max_balance_per_account: Option<U128>,
+    pub min_remaining_balance: Option<U128>,
+    pub supply_warning_bps: Option<u16>,
+    pub mint_unlock_ns: Option<U64>,
+    pub max_batch_size: u32,
+    pub volume_cap_per_epoch: Option<U128>,
+    pub resolve_gas: Gas,
+    pub metadata_change_cooldown_ns: Option<U64>,
+    pub account_storage_usage: StorageUsage,
+    pub inactivity_threshold_ns: Option<U64>,
+    pub claim_window_ns: Option<U64>,
 }
+
 #[derive(BorshSerialize, BorshStorageKey)]
 #[borsh(crate = "near_sdk::borsh")]
 enum StorageKey {
     FungibleToken,
     Metadata,
+    Vesting,
+    VestingSchedules { account_hash: Vec<u8> },
+    FeeExempt,
+    RecentMemos,
+    Minters,
+    ExcludedAccounts,
+    Allowances,
+    FrozenAccounts,
+    PermitKeys,
+    PermitNonces,
+    LastActive,
+    PendingClaims,
+    AllowanceExpiries,
+    Holders,
+    BalanceLocks,
+    SelfLocks,
+    AllowedReceivers,
+    StorageCovered,
+    HoldingCapExempt,
+    SupplyHistory,
+    LastActiveEpoch,
+    TransferCallReceivers,
+    AllowanceSpenders,
 }
 
 #[near]
 impl Contract {
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// the given fungible token metadata.
+    /// the given fungible token metadata. If `mint_unlock_ns` is set, no tokens can be minted
+    /// before that block timestamp. Fees and recovered tokens go to `treasury_id`, the owner
+    /// by default.
     #[init]
-    pub fn new(owner_id: AccountId, total_supply: U128, metadata: FungibleTokenMetadata) -> Self {
-        require!(!env::state_exists(), "Already initialized");
+    pub fn new(
+        owner_id: AccountId,
+        total_supply: U128,
+        metadata: FungibleTokenMetadata,
+        mint_unlock_ns: Option<U64>,
+        treasury_id: Option<AccountId>,
+    ) -> Self {
+        require!(!env::state_exists(), ERR_ALREADY_INITIALIZED);
         metadata.assert_valid();
+        let token = FungibleToken::new(StorageKey::FungibleToken);
+        let base_account_storage_usage = token.account_storage_usage;
         let mut this = Self {
             owner_id: owner_id.clone(),
-            token: FungibleToken::new(StorageKey::FungibleToken),
+            token,
+            base_account_storage_usage,
+            storage_covered: LookupMap::new(StorageKey::StorageCovered),
             metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            deployed_at_ns: env::block_timestamp(),
+            metadata_change_cooldown_ns: 0,
+            last_metadata_change_ns: None,
+            scheduled_metadata: None,
+            metadata_locked: false,
+            legal_reference: None,
+            vesting: LookupMap::new(StorageKey::Vesting),
+            transfer_fee_bps: 0,
+            transfer_burn_bps: 0,
+            transfer_reward_bps: 0,
+            reward_pool_id: None,
+            transfer_precision: None,
+            fee_rounding: FeeRounding::Down,
+            fee_mode: FeeMode::Collect,
+            fee_exempt: UnorderedSet::new(StorageKey::FeeExempt),
+            finalized: false,
+            memo_log_enabled: false,
+            rich_events: false,
+            recent_memos: LookupMap::new(StorageKey::RecentMemos),
+            minters: UnorderedSet::new(StorageKey::Minters),
+            excluded_accounts: UnorderedSet::new(StorageKey::ExcludedAccounts),
+            burn_address: None,
+            holders: UnorderedSet::new(StorageKey::Holders),
+            nonzero_holders: 0,
+            distribution: Vec::new(),
+            rebase_progress: None,
+            transfer_observer: None,
+            max_supply: None,
+            max_balance_per_account: None,
+            min_remaining_balance: None,
+            holding_cap_exempt: UnorderedSet::new(StorageKey::HoldingCapExempt),
+            supply_warning_bps: None,
+            supply_history: Vector::new(StorageKey::SupplyHistory),
+            supply_checkpoints: 0,
+            total_minted: 0,
+            total_burned: 0,
+            allowances: LookupMap::new(StorageKey::Allowances),
+            allowance_expiries: LookupMap::new(StorageKey::AllowanceExpiries),
+            allowance_spenders: LookupMap::new(StorageKey::AllowanceSpenders),
+            mint_unlock_ns: mint_unlock_ns.map(|mint_unlock_ns| mint_unlock_ns.0),
+            frozen_accounts: UnorderedSet::new(StorageKey::FrozenAccounts),
+            balance_locks: LookupMap::new(StorageKey::BalanceLocks),
+            self_locks: LookupMap::new(StorageKey::SelfLocks),
+            allowed_receivers: LookupMap::new(StorageKey::AllowedReceivers),
+            paused: false,
+            minting_paused: false,
+            pause_reason: None,
+            treasury_id: treasury_id.clone().unwrap_or_else(|| owner_id.clone()),
+            vesting_escrowed: 0,
+            storage_initialized: false,
+            reject_storage_overpayment: false,
+            migration_mode: true,
+            permit_keys: LookupMap::new(StorageKey::PermitKeys),
+            permit_nonces: LookupMap::new(StorageKey::PermitNonces),
+            resolve_gas: transfer_call::DEFAULT_GAS_FOR_RESOLVE_TRANSFER,
+            allow_zero_transfers: false,
+            burn_unresolved_refunds: true,
+            restrict_transfer_call: false,
+            transfer_call_receivers: UnorderedSet::new(StorageKey::TransferCallReceivers),
+            activity_tracking_enabled: false,
+            last_active: LookupMap::new(StorageKey::LastActive),
+            last_active_epoch: LookupMap::new(StorageKey::LastActiveEpoch),
+            active_accounts_epoch_height: 0,
+            active_accounts_count: 0,
+            inactivity_threshold_ns: None,
+            unregistered_receiver_policy: UnregisteredReceiverPolicy::Reject,
+            pending_claims: LookupMap::new(StorageKey::PendingClaims),
+            claim_window_ns: None,
+            pending_claims_escrowed: 0,
+            max_batch_size: batch::DEFAULT_MAX_BATCH_SIZE,
+            volume_cap_per_epoch: None,
+            volume_epoch_height: 0,
+            epoch_volume: 0,
         };
-        this.token.internal_register_account(&owner_id);
-        this.token.internal_deposit(&owner_id, total_supply.into());
+        this.internal_register_account(&owner_id);
+        this.internal_credit(&owner_id, total_supply.into());
+        this.record_minted(total_supply.0);
+        if let Some(treasury_id) = treasury_id.filter(|treasury_id| *treasury_id != owner_id) {
+            this.internal_register_account(&treasury_id);
+        }
 
         near_contract_standards::fungible_token::events::FtMint {
             owner_id: &owner_id,
@@ -55,38 +328,392 @@ impl Contract {
         this
     }
 
+    /// Replaces the token metadata. The decimals can't change, and the name and symbol can
+    /// change at most once per metadata change cooldown.
     #[payable]
     pub fn update_metadata(&mut self, metadata: FungibleTokenMetadata) {
         assert_one_yocto();
-        require!(self.owner_id == env::predecessor_account_id(), "Not allow");
-        metadata.assert_valid();
-        let current_metadata = self.metadata.get().unwrap();
+        self.assert_owner();
+        self.internal_set_metadata(metadata);
+    }
+
+    /// Moves the metadata to another version of the NEP-148 spec, e.g. `ft-1.1.0`, leaving
+    /// every other field untouched.
+    #[payable]
+    pub fn upgrade_metadata_spec(&mut self, new_spec: String) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_metadata_not_locked();
+        let version = new_spec
+            .strip_prefix("ft-")
+            .unwrap_or_else(|| env::panic_str(ERR_INVALID_SPEC));
+        let parts: Vec<&str> = version.split('.').collect();
         require!(
-            current_metadata.decimals == metadata.decimals,
-            "Can't change decimals"
+            parts.len() == 3
+                && parts
+                    .iter()
+                    .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())),
+            ERR_INVALID_SPEC
+        );
+        let mut metadata = self.ft_metadata();
+        log!(
+            "Metadata spec upgraded from {} to {}",
+            metadata.spec,
+            new_spec
         );
+        metadata.spec = new_spec;
         self.metadata.set(&metadata);
     }
 
+    /// Sets the minimum time between two changes of the token name or symbol. Changes of the
+    /// other metadata fields aren't limited.
+    #[payable]
+    pub fn set_metadata_change_cooldown(&mut self, cooldown_ns: U64) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_metadata_not_locked();
+        self.metadata_change_cooldown_ns = cooldown_ns.0;
+    }
+
+    pub fn get_metadata_change_cooldown(&self) -> U64 {
+        self.metadata_change_cooldown_ns.into()
+    }
+
+    /// Makes the token metadata permanently immutable and drops any scheduled update. Unlike
+    /// `finalize`, every other admin method, minting included, keeps working. This can't be
+    /// undone.
+    #[payable]
+    pub fn lock_metadata(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Metadata locked by {}", self.owner_id);
+        self.metadata_locked = true;
+        self.scheduled_metadata = None;
+    }
+
+    pub fn is_metadata_locked(&self) -> bool {
+        self.metadata_locked
+    }
+
+    /// Returns only the token symbol, for clients that don't need the full metadata.
+    pub fn ft_symbol(&self) -> String {
+        self.ft_metadata().symbol
+    }
+
+    /// Returns only the token decimals, for clients that don't need the full metadata.
+    pub fn ft_decimals(&self) -> u8 {
+        self.ft_metadata().decimals
+    }
+
+    /// Returns the NEP standards implemented by the contract, following the NEP-330 naming.
+    pub fn supported_standards(&self) -> Vec<String> {
+        ["nep141", "nep145", "nep148", "nep297"]
+            .iter()
+            .map(|standard| standard.to_string())
+            .collect()
+    }
+
     #[payable]
     pub fn update_owner(&mut self, new_owner: AccountId) -> bool {
         assert_one_yocto();
-        require!(
-            env::predecessor_account_id() == self.owner_id,
-            "Owner's method"
-        );
-        require!(!new_owner.as_str().is_empty(), "New owner cannot be empty");
+        self.assert_owner();
+        require!(!new_owner.as_str().is_empty(), ERR_EMPTY_OWNER);
+        self.assert_valid_external_account(&new_owner);
         log!("Owner updated from {} to {}", self.owner_id, new_owner);
         self.owner_id = new_owner;
         true
     }
+
+    /// `ft_transfer` that panics once the block timestamp is past `deadline_ns`, so a
+    /// transfer stuck in a relayer queue can't execute much later than intended.
+    #[payable]
+    pub fn ft_transfer_with_deadline(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        deadline_ns: Option<U64>,
+    ) {
+        if let Some(deadline_ns) = deadline_ns {
+            require!(env::block_timestamp() <= deadline_ns.0, ERR_DEADLINE_PASSED);
+        }
+        self.ft_transfer(receiver_id, amount, memo);
+    }
+
+    /// Returns the block timestamp at which the contract was initialized.
+    pub fn deployed_at(&self) -> U64 {
+        self.deployed_at_ns.into()
+    }
+
+    /// Makes the contract permanently immutable: every admin method panics afterwards,
+    /// minting included, while transfers keep working. This can't be undone.
+    #[payable]
+    pub fn finalize(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Contract finalized by {}", self.owner_id);
+        self.finalized = true;
+    }
+
+    /// Returns `true` once the admin surface of the contract is permanently locked.
+    pub fn is_finalized(&self) -> bool {
+        self.finalized
+    }
+
+    pub fn get_config(&self) -> ContractConfig {
+        ContractConfig {
+            owner_id: self.owner_id.clone(),
+            treasury_id: self.treasury_id.clone(),
+            transfer_fee_bps: self.transfer_fee_bps,
+            finalized: self.finalized,
+        }
+    }
+
+    pub fn get_limits(&self) -> Limits {
+        Limits {
+            transfer_fee_bps: self.transfer_fee_bps,
+            transfer_burn_bps: self.transfer_burn_bps,
+            transfer_reward_bps: self.transfer_reward_bps,
+            transfer_precision: self.transfer_precision,
+            max_supply: self.max_supply.map(U128),
+            max_balance_per_account: self.max_balance_per_account.map(U128),
+            min_remaining_balance: self.min_remaining_balance.map(U128),
+            supply_warning_bps: self.supply_warning_bps,
+            mint_unlock_ns: self.mint_unlock_ns.map(U64),
+            max_batch_size: self.max_batch_size,
+            volume_cap_per_epoch: self.volume_cap_per_epoch.map(U128),
+            resolve_gas: self.resolve_gas,
+            metadata_change_cooldown_ns: Some(self.metadata_change_cooldown_ns)
+                .filter(|cooldown_ns| *cooldown_ns > 0)
+                .map(U64),
+            account_storage_usage: self.token.account_storage_usage,
+            inactivity_threshold_ns: self.inactivity_threshold_ns.map(U64),
+            claim_window_ns: self.claim_window_ns.map(U64),
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_not_finalized(&self) {
+        require!(!self.finalized, ERR_FINALIZED);
+    }
+
+    pub(crate) fn assert_metadata_not_locked(&self) {
+        require!(!self.metadata_locked, ERR_METADATA_LOCKED);
+    }
+
+    /// Panics unless called by the owner of a contract that wasn't finalized.
+    pub(crate) fn assert_owner(&self) {
+        self.assert_not_finalized();
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            ERR_NOT_OWNER
+        );
+    }
+
+    /// Checks `metadata` against the current one and stores it. The decimals can't change,
+    /// and the name and symbol can change at most once per metadata change cooldown.
+    pub(crate) fn internal_set_metadata(&mut self, metadata: FungibleTokenMetadata) {
+        self.assert_metadata_not_locked();
+        metadata.assert_valid();
+        self.assert_same_decimals(&metadata);
+        let current_metadata = self.ft_metadata();
+        if current_metadata.name != metadata.name || current_metadata.symbol != metadata.symbol {
+            let now = env::block_timestamp();
+            if let Some(last_change_ns) = self.last_metadata_change_ns {
+                require!(
+                    now >= last_change_ns.saturating_add(self.metadata_change_cooldown_ns),
+                    ERR_METADATA_COOLDOWN
+                );
+            }
+            self.last_metadata_change_ns = Some(now);
+        }
+        self.metadata.set(&metadata);
+    }
+
+    pub(crate) fn assert_same_decimals(&self, metadata: &FungibleTokenMetadata) {
+        require!(
+            self.ft_metadata().decimals == metadata.decimals,
+            ERR_DECIMALS_CHANGED
+        );
+    }
+
+    /// Moves `amount` from `sender_id` to `receiver_id`, sending the transfer fee, if any,
+    /// to the fee collector, or burning it in `FeeMode::Burn`, and burning the transfer burn
+    /// share. Panics if the contract is paused, the sender is frozen or would keep less than
+    /// the min remaining balance. Returns the amount credited to the receiver.
+    pub(crate) fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> Balance {
+        self.assert_min_remaining(sender_id, amount);
+        self.internal_transfer_without_min_remaining(sender_id, receiver_id, amount, memo)
+    }
+
+    /// `internal_transfer` without the min remaining balance check, for `ft_transfer_all`.
+    pub(crate) fn internal_transfer_without_min_remaining(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> Balance {
+        self.assert_not_paused();
+        self.assert_not_frozen(sender_id);
+        self.assert_not_self_locked(sender_id);
+        self.assert_receiver_allowed(sender_id, receiver_id);
+        self.record_memo(sender_id, memo.as_deref());
+        let legs = self.internal_move(sender_id, receiver_id, amount, memo);
+        emit_transfers(sender_id, &legs);
+        legs[0].amount
+    }
+
+    /// Pays `amount` held by the contract account for vesting grants or held transfers out
+    /// to `receiver_id`, free of fees. Panics if the contract is paused or the receiver would
+    /// go above the max balance per account.
+    pub(crate) fn internal_release_escrow(
+        &mut self,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: &str,
+    ) {
+        self.assert_not_paused();
+        self.assert_within_max_holding(receiver_id, amount);
+        self.internal_token_transfer(
+            &env::current_account_id(),
+            receiver_id,
+            amount,
+            Some(memo.to_string()),
+        );
+    }
+
+    /// Moves the balances of a transfer without emitting events, notifies the transfer
+    /// observer and returns the credited legs, so callers can report several transfers in a
+    /// single `FtTransfer` event.
+    pub(crate) fn internal_move(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> Vec<TransferLeg> {
+        require!(sender_id != receiver_id, ERR_SELF_TRANSFER);
+        require!(amount > 0, ERR_ZERO_AMOUNT);
+        self.assert_transfer_precision(amount);
+        self.assert_unlocked(sender_id, amount);
+        self.record_volume(amount);
+        let Splits {
+            to_receiver: credited,
+            to_collector: fee,
+            burned,
+            to_reward_pool,
+        } = self.compute_splits(sender_id, receiver_id, amount);
+        self.assert_within_max_holding(receiver_id, credited);
+        self.internal_debit(sender_id, amount);
+        self.internal_credit(receiver_id, credited);
+        self.notify_transfer_observer(sender_id, receiver_id, credited);
+        self.record_activity(sender_id, receiver_id);
+        self.check_balance_zeroed(sender_id);
+        self.emit_transfer_detail(sender_id, receiver_id, amount, fee, burned, credited);
+        if burned > 0 {
+            FtBurn {
+                owner_id: sender_id,
+                amount: burned.into(),
+                memo: Some("transfer burn"),
+            }
+            .emit();
+            self.record_burned(burned);
+        }
+
+        let mut legs = vec![TransferLeg {
+            receiver_id: receiver_id.clone(),
+            amount: credited,
+            memo,
+        }];
+        if fee > 0 {
+            match self.fee_mode {
+                FeeMode::Collect => {
+                    let fee_collector = self.fee_collector().clone();
+                    self.internal_credit(&fee_collector, fee);
+                    legs.push(TransferLeg {
+                        receiver_id: fee_collector,
+                        amount: fee,
+                        memo: Some("transfer fee".to_string()),
+                    });
+                }
+                // the fee was withdrawn from the sender with the rest of the amount and is
+                // simply not credited anywhere
+                FeeMode::Burn => {
+                    FtBurn {
+                        owner_id: sender_id,
+                        amount: fee.into(),
+                        memo: Some("transfer fee"),
+                    }
+                    .emit();
+                    self.record_burned(fee);
+                }
+            }
+        }
+        if to_reward_pool > 0 {
+            let reward_pool_id = self
+                .reward_pool_id
+                .clone()
+                .unwrap_or_else(|| env::panic_str(ERR_NO_REWARD_POOL));
+            self.internal_credit(&reward_pool_id, to_reward_pool);
+            legs.push(TransferLeg {
+                receiver_id: reward_pool_id,
+                amount: to_reward_pool,
+                memo: Some("reward pool contribution".to_string()),
+            });
+        }
+        legs
+    }
+}
+
+/// A single credit made by a transfer, reported as one entry of an `FtTransfer` event.
+pub(crate) struct TransferLeg {
+    pub receiver_id: AccountId,
+    pub amount: Balance,
+    pub memo: Option<String>,
+}
+
+/// Emits a single `FtTransfer` event covering all `legs` sent by `sender_id`.
+pub(crate) fn emit_transfers(sender_id: &AccountId, legs: &[TransferLeg]) {
+    let events: Vec<FtTransfer> = legs
+        .iter()
+        .map(|leg| FtTransfer {
+            old_owner_id: sender_id,
+            new_owner_id: &leg.receiver_id,
+            amount: leg.amount.into(),
+            memo: leg.memo.as_deref(),
+        })
+        .collect();
+    FtTransfer::emit_many(&events);
 }
 
 #[near]
 impl FungibleTokenCore for Contract {
+    /// Transfers to unregistered receivers follow the `UnregisteredReceiverPolicy`.
     #[payable]
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
-        self.token.ft_transfer(receiver_id, amount, memo)
+        let sender_id = env::predecessor_account_id();
+        require!(sender_id != receiver_id, ERR_SELF_TRANSFER);
+        match self.unregistered_receiver_policy {
+            UnregisteredReceiverPolicy::AutoRegister => {
+                return self.ft_transfer_and_register(receiver_id, amount, memo);
+            }
+            UnregisteredReceiverPolicy::HoldForClaim
+                if !self.token.accounts.contains_key(&receiver_id) =>
+            {
+                assert_one_yocto();
+                return self.internal_hold_for_claim(&sender_id, &receiver_id, amount.0, memo);
+            }
+            _ => assert_one_yocto(),
+        }
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
     }
 
     #[payable]
@@ -97,7 +724,11 @@ impl FungibleTokenCore for Contract {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        self.token.ft_transfer_call(receiver_id, amount, memo, msg)
+        let sender_id = env::predecessor_account_id();
+        require!(amount.0 > 0 || self.allow_zero_transfers, ERR_ZERO_AMOUNT);
+        self.assert_transfer_call_receiver_approved(&receiver_id);
+        transfer_call::parse_transfer_call_msg(&msg, amount.0);
+        self.internal_transfer_call(sender_id, receiver_id, amount, memo, msg)
     }
 
     fn ft_total_supply(&self) -> U128 {
@@ -111,6 +742,8 @@ impl FungibleTokenCore for Contract {
 
 #[near]
 impl FungibleTokenResolver for Contract {
+    /// The part of the transfer refunded by the receiver is taken back out of the epoch
+    /// volume and reported to the transfer observer as a transfer back to the sender.
     #[private]
     fn ft_resolve_transfer(
         &mut self,
@@ -118,12 +751,27 @@ impl FungibleTokenResolver for Contract {
         receiver_id: AccountId,
         amount: U128,
     ) -> U128 {
-        let (used_amount, burned_amount) =
-            self.token
-                .internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
-        if burned_amount > 0 {
-            log!("Account @{} burned {}", sender_id, burned_amount);
-        }
+        let used_amount = if !self.burn_unresolved_refunds
+            && !self.token.accounts.contains_key(&sender_id)
+        {
+            self.internal_hold_refund(&sender_id, &receiver_id, amount.0)
+        } else {
+            let sender_balance = self.token.accounts.get(&sender_id).unwrap_or(0);
+            let receiver_balance = self.token.accounts.get(&receiver_id).unwrap_or(0);
+            let (used_amount, burned_amount) =
+                self.token
+                    .internal_ft_resolve_transfer(&sender_id, receiver_id.clone(), amount);
+            self.track_holder_balance(&sender_id, sender_balance);
+            self.track_holder_balance(&receiver_id, receiver_balance);
+            if burned_amount > 0 {
+                log!("Account @{} burned {}", sender_id, burned_amount);
+                self.record_burned(burned_amount);
+            } else if used_amount < amount.0 {
+                self.notify_transfer_observer(&receiver_id, &sender_id, amount.0 - used_amount);
+            }
+            used_amount
+        };
+        self.release_volume(amount.0 - used_amount);
         used_amount.into()
     }
 }
@@ -136,23 +784,29 @@ impl StorageManagement for Contract {
         account_id: Option<AccountId>,
         registration_only: Option<bool>,
     ) -> StorageBalance {
-        self.token.storage_deposit(account_id, registration_only)
+        let registered_id = account_id
+            .clone()
+            .unwrap_or_else(env::predecessor_account_id);
+        self.assert_not_storage_overpayment(env::attached_deposit());
+        let newly_registered = !self.token.accounts.contains_key(&registered_id);
+        self.token.storage_deposit(account_id, registration_only);
+        self.holders.insert(&registered_id);
+        if newly_registered {
+            self.storage_covered
+                .insert(&registered_id, &self.token.account_storage_usage);
+        }
+        self.internal_storage_balance(&registered_id)
     }
 
     #[payable]
     fn storage_withdraw(&mut self, amount: Option<NearToken>) -> StorageBalance {
-        self.token.storage_withdraw(amount)
+        self.token.storage_withdraw(amount);
+        self.internal_storage_balance(&env::predecessor_account_id())
     }
 
     #[payable]
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
-        #[allow(unused_variables)]
-        if let Some((account_id, balance)) = self.token.internal_storage_unregister(force) {
-            log!("Closed @{} with {}", account_id, balance);
-            true
-        } else {
-            false
-        }
+        self.internal_unregister(force).is_some()
     }
 
     fn storage_balance_bounds(&self) -> StorageBalanceBounds {
@@ -160,69 +814,67 @@ impl StorageManagement for Contract {
     }
 
     fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
-        self.token.storage_balance_of(account_id)
+        self.token
+            .accounts
+            .contains_key(&account_id)
+            .then(|| self.internal_storage_balance(&account_id))
     }
 }
 
 #[near]
 impl FungibleTokenMetadataProvider for Contract {
+    /// Metadata is always set by `new`, so a missing value is a broken state: it panics instead
+    /// of returning an empty default clients could mistake for real metadata.
     fn ft_metadata(&self) -> FungibleTokenMetadata {
         self.metadata
             .get()
-            .unwrap_or_else(|| FungibleTokenMetadata {
-                spec: FT_METADATA_SPEC.to_string(),
-                name: String::default(),
-                symbol: String::default(),
-                icon: None,
-                reference: None,
-                reference_hash: None,
-                decimals: 0,
-            })
+            .unwrap_or_else(|| env::panic_str(ERR_METADATA_NOT_SET))
     }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
+    use near_contract_standards::fungible_token::metadata::FT_METADATA_SPEC;
     use near_contract_standards::fungible_token::Balance;
     use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::{testing_env, Gas};
+    use near_sdk::testing_env;
 
     use super::*;
 
-    const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+    pub(crate) const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
 
-    fn current() -> AccountId {
+    pub(crate) fn current() -> AccountId {
         accounts(0)
     }
 
-    fn owner() -> AccountId {
+    pub(crate) fn owner() -> AccountId {
         accounts(1)
     }
 
-    fn user1() -> AccountId {
+    pub(crate) fn user1() -> AccountId {
         accounts(2)
     }
 
-    fn user2() -> AccountId {
+    pub(crate) fn user2() -> AccountId {
         accounts(3)
     }
 
-    fn setup() -> (Contract, VMContextBuilder) {
+    pub(crate) fn metadata() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Example NEAR fungible token".to_string(),
+            symbol: "EXAMPLE".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        }
+    }
+
+    pub(crate) fn setup() -> (Contract, VMContextBuilder) {
         let mut context = VMContextBuilder::new();
 
-        let contract = Contract::new(
-            owner(),
-            TOTAL_SUPPLY.into(),
-            FungibleTokenMetadata {
-                spec: FT_METADATA_SPEC.to_string(),
-                name: "Example NEAR fungible token".to_string(),
-                symbol: "EXAMPLE".to_string(),
-                icon: None,
-                reference: None,
-                reference_hash: None,
-                decimals: 24,
-            },
-        );
+        let contract = Contract::new(owner(), TOTAL_SUPPLY.into(), metadata(), None, None);
 
         context.storage_usage(env::storage_usage());
         context.current_account_id(current());
@@ -232,6 +884,18 @@ mod tests {
         (contract, context)
     }
 
+    pub(crate) fn register_user(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        account_id: AccountId,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(account_id)
+            .attached_deposit(contract.storage_balance_bounds().min)
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
     #[test]
     fn test_new() {
         let (contract, _) = setup();
@@ -240,6 +904,16 @@ mod tests {
         assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY);
     }
 
+    #[test]
+    fn test_deployed_at() {
+        testing_env!(VMContextBuilder::new()
+            .block_timestamp(1_700_000_000_000_000_000)
+            .build());
+        let contract = Contract::new(owner(), TOTAL_SUPPLY.into(), metadata(), None, None);
+
+        assert_eq!(contract.deployed_at().0, 1_700_000_000_000_000_000);
+    }
+
     #[test]
     fn test_metadata() {
         let (contract, _) = setup();
@@ -251,6 +925,43 @@ mod tests {
         assert!(!contract.ft_metadata().symbol.is_empty());
     }
 
+    #[test]
+    fn test_metadata_matches_new() {
+        let (contract, _) = setup();
+
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.spec, FT_METADATA_SPEC);
+        assert_eq!(metadata.name, "Example NEAR fungible token");
+        assert_eq!(metadata.symbol, "EXAMPLE");
+    }
+
+    #[test]
+    fn test_supported_standards() {
+        let (contract, _) = setup();
+
+        let standards = contract.supported_standards();
+        for standard in ["nep141", "nep145", "nep148"] {
+            assert!(standards.contains(&standard.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_symbol_and_decimals_match_new() {
+        let (contract, _) = setup();
+
+        assert_eq!(contract.ft_symbol(), metadata().symbol);
+        assert_eq!(contract.ft_decimals(), metadata().decimals);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token metadata is not set")]
+    fn test_metadata_panics_when_unset() {
+        let (mut contract, _) = setup();
+        contract.metadata.remove();
+
+        contract.ft_metadata();
+    }
+
     #[test]
     #[should_panic(expected = "The contract is not initialized")]
     fn test_default_panics() {
@@ -354,7 +1065,7 @@ mod tests {
             .attached_deposit(NearToken::from_yoctonear(1))
             .build());
 
-        assert_eq!(contract.storage_unregister(None), true);
+        assert!(contract.storage_unregister(None));
 
         assert!(contract.storage_balance_of(user1()).is_none());
     }
@@ -391,7 +1102,7 @@ mod tests {
             .build());
 
         // "false" indicates that the account wasn't registered
-        assert_eq!(contract.storage_unregister(None), false);
+        assert!(!contract.storage_unregister(None));
     }
 
     #[should_panic]
@@ -452,7 +1163,7 @@ mod tests {
 
         // force to unregister no matter what
         // this reduces total supply because user's tokens are burnt
-        assert_eq!(contract.storage_unregister(Some(true)), true);
+        assert!(contract.storage_unregister(Some(true)));
 
         assert!(contract.storage_balance_of(user1()).is_none());
         assert_eq!(contract.ft_balance_of(user1()).0, 0);
@@ -557,7 +1268,36 @@ mod tests {
         assert_eq!(contract.ft_balance_of(user1()).0, transfer_amount);
     }
 
-    #[should_panic]
+    #[test]
+    fn test_transfer_before_deadline() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(1_000)
+            .build());
+        contract.ft_transfer_with_deadline(user1(), 100.into(), None, Some(1_000.into()));
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer deadline passed")]
+    fn test_transfer_after_deadline() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(1_001)
+            .build());
+        contract.ft_transfer_with_deadline(user1(), 100.into(), None, Some(1_000.into()));
+    }
+
+    #[should_panic(expected = "Cannot transfer to self")]
     #[test]
     fn test_transfer_panics_on_self_receiver() {
         let (mut contract, mut context) = setup();
@@ -699,7 +1439,7 @@ mod tests {
         assert_eq!(contract.ft_balance_of(user1()).0, transfer_amount);
     }
 
-    #[should_panic]
+    #[should_panic(expected = "Cannot transfer to self")]
     #[test]
     fn test_transfer_call_panics_on_self_receiver() {
         let (mut contract, mut context) = setup();
@@ -863,4 +1603,303 @@ mod tests {
 
         contract.update_owner(new_owner.clone());
     }
+
+    fn finalize(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.finalize();
+    }
+
+    #[test]
+    fn test_is_finalized() {
+        let (mut contract, mut context) = setup();
+        assert!(!contract.is_finalized());
+        assert!(!contract.get_config().finalized);
+
+        finalize(&mut contract, &mut context);
+
+        assert!(contract.is_finalized());
+        assert!(contract.get_config().finalized);
+    }
+
+    #[test]
+    fn test_get_config() {
+        let (contract, _) = setup();
+
+        let config = contract.get_config();
+        assert_eq!(config.owner_id, owner());
+        assert_eq!(config.treasury_id, owner());
+        assert_eq!(config.transfer_fee_bps, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_finalize_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.finalize();
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_update_metadata_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        let metadata = contract.ft_metadata();
+        contract.update_metadata(metadata);
+    }
+
+    /// Renames the token at `timestamp`, with a one hour cooldown between renames.
+    fn rename_at(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        name: &str,
+        timestamp: u64,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.set_metadata_change_cooldown(3_600_000_000_000.into());
+        let mut metadata = contract.ft_metadata();
+        metadata.name = name.to_string();
+        contract.update_metadata(metadata);
+    }
+
+    #[test]
+    #[should_panic(expected = "Name and symbol can't change again before the cooldown ends")]
+    fn test_update_metadata_within_cooldown() {
+        let (mut contract, mut context) = setup();
+        rename_at(&mut contract, &mut context, "First", 1_000);
+
+        rename_at(
+            &mut contract,
+            &mut context,
+            "Second",
+            1_000 + 3_599_999_999_999,
+        );
+    }
+
+    #[test]
+    fn test_update_metadata_after_cooldown() {
+        let (mut contract, mut context) = setup();
+        rename_at(&mut contract, &mut context, "First", 1_000);
+
+        // changing only the reference isn't limited
+        let mut metadata = contract.ft_metadata();
+        metadata.reference = Some("https://example.com/token.json".to_string());
+        metadata.reference_hash = Some(vec![0; 32].into());
+        contract.update_metadata(metadata);
+
+        rename_at(
+            &mut contract,
+            &mut context,
+            "Second",
+            1_000 + 3_600_000_000_000,
+        );
+        assert_eq!(contract.ft_metadata().name, "Second");
+        assert!(contract.ft_metadata().reference.is_some());
+    }
+
+    #[test]
+    fn test_get_limits() {
+        let (mut contract, mut context) = setup();
+        let defaults = contract.get_limits();
+        assert_eq!(defaults.max_supply, None);
+        assert_eq!(defaults.volume_cap_per_epoch, None);
+        assert_eq!(defaults.metadata_change_cooldown_ns, None);
+        assert_eq!(defaults.max_batch_size, 100);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_fee(25);
+        contract.set_max_supply(Some(TOTAL_SUPPLY.into()));
+        contract.set_max_batch_size(20);
+        contract.set_volume_cap_per_epoch(Some(1_000.into()));
+        contract.set_transfer_precision(Some(2));
+        contract.set_metadata_change_cooldown(60.into());
+
+        let limits = contract.get_limits();
+        assert_eq!(
+            limits,
+            Limits {
+                transfer_fee_bps: 25,
+                max_supply: Some(TOTAL_SUPPLY.into()),
+                max_batch_size: 20,
+                volume_cap_per_epoch: Some(1_000.into()),
+                transfer_precision: Some(2),
+                metadata_change_cooldown_ns: Some(60.into()),
+                ..defaults
+            }
+        );
+    }
+
+    #[test]
+    fn test_upgrade_metadata_spec() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.upgrade_metadata_spec("ft-1.1.0".to_string());
+
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.spec, "ft-1.1.0");
+        assert_eq!(metadata.name, "Example NEAR fungible token");
+        assert_eq!(metadata.symbol, "EXAMPLE");
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata spec must look like ft-1.0.0")]
+    fn test_upgrade_metadata_spec_rejects_empty_spec() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.upgrade_metadata_spec("".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata spec must look like ft-1.0.0")]
+    fn test_upgrade_metadata_spec_rejects_malformed_version() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.upgrade_metadata_spec("ft-1.x".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract finalized")]
+    fn test_update_metadata_fails_after_finalize() {
+        let (mut contract, mut context) = setup();
+        finalize(&mut contract, &mut context);
+
+        let metadata = contract.ft_metadata();
+        contract.update_metadata(metadata);
+    }
+
+    fn lock_metadata(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.lock_metadata();
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is locked")]
+    fn test_update_metadata_fails_after_lock() {
+        let (mut contract, mut context) = setup();
+        assert!(!contract.is_metadata_locked());
+        lock_metadata(&mut contract, &mut context);
+        assert!(contract.is_metadata_locked());
+
+        let mut metadata = contract.ft_metadata();
+        metadata.name = "Renamed token".to_string();
+        contract.update_metadata(metadata);
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is locked")]
+    fn test_upgrade_metadata_spec_fails_after_lock() {
+        let (mut contract, mut context) = setup();
+        lock_metadata(&mut contract, &mut context);
+
+        contract.upgrade_metadata_spec("ft-1.1.0".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Metadata is locked")]
+    fn test_schedule_metadata_update_fails_after_lock() {
+        let (mut contract, mut context) = setup();
+        lock_metadata(&mut contract, &mut context);
+
+        contract.schedule_metadata_update(metadata(), 0.into());
+    }
+
+    #[test]
+    fn test_lock_metadata_drops_scheduled_update() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.schedule_metadata_update(metadata(), 0.into());
+        contract.lock_metadata();
+
+        assert!(contract.get_scheduled_metadata().is_none());
+    }
+
+    #[test]
+    fn test_mint_after_metadata_lock() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        lock_metadata(&mut contract, &mut context);
+
+        contract.add_minter(owner());
+        contract.mint(user1(), 100.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 100);
+        assert!(!contract.is_finalized());
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract finalized")]
+    fn test_update_owner_fails_after_finalize() {
+        let (mut contract, mut context) = setup();
+        finalize(&mut contract, &mut context);
+
+        contract.update_owner(user1());
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract finalized")]
+    fn test_set_transfer_fee_fails_after_finalize() {
+        let (mut contract, mut context) = setup();
+        finalize(&mut contract, &mut context);
+
+        contract.set_transfer_fee(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract finalized")]
+    fn test_finalize_twice_fails() {
+        let (mut contract, mut context) = setup();
+        finalize(&mut contract, &mut context);
+
+        contract.finalize();
+    }
+
+    #[test]
+    fn test_transfer_after_finalize() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        finalize(&mut contract, &mut context);
+
+        let transfer_amount = TOTAL_SUPPLY / 10;
+        contract.ft_transfer(user1(), transfer_amount.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, transfer_amount);
+    }
 }
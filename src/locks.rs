@@ -0,0 +1,239 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::{ERR_BALANCE_LOCKED, ERR_LOCK_ABOVE_BALANCE, ERR_SELF_LOCKED, ERR_ZERO_AMOUNT};
+use crate::{Contract, ContractExt};
+
+/// Part of a balance that can't be moved before a timestamp, e.g. pledged as collateral.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceLock {
+    pub amount: U128,
+    pub until_ns: U64,
+}
+
+#[near]
+impl Contract {
+    /// Locks `amount` of the balance of `account_id` until the block timestamp `until_ns`.
+    /// Replaces any previous lock of the account.
+    #[payable]
+    pub fn lock_balance(&mut self, account_id: AccountId, amount: U128, until_ns: U64) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        require!(
+            amount.0 <= self.token.accounts.get(&account_id).unwrap_or(0),
+            ERR_LOCK_ABOVE_BALANCE
+        );
+        log!("Locked {} of {} until {}", amount.0, account_id, until_ns.0);
+        self.balance_locks
+            .insert(&account_id, &BalanceLock { amount, until_ns });
+    }
+
+    /// Releases the lock of `account_id` before it expires.
+    #[payable]
+    pub fn unlock_balance(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.balance_locks.remove(&account_id).is_some()
+    }
+
+    /// Returns the amount of the balance of `account_id` that is currently locked. Expired
+    /// locks count as zero.
+    pub fn locked_balance_of(&self, account_id: AccountId) -> U128 {
+        self.internal_locked_balance(&account_id).into()
+    }
+
+    pub fn get_balance_lock(&self, account_id: AccountId) -> Option<BalanceLock> {
+        self.balance_locks.get(&account_id)
+    }
+
+    /// Blocks (or allows again) every outgoing transfer of the caller, e.g. while a leaked
+    /// key is being rotated. Only the account itself can toggle its lock.
+    #[payable]
+    pub fn set_self_lock(&mut self, locked: bool) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        if locked {
+            require!(
+                self.token.accounts.contains_key(&account_id),
+                format!("The account {} is not registered", account_id)
+            );
+            self.assert_storage_funded(&account_id);
+            self.self_locks.insert(&account_id, &true);
+        } else {
+            self.self_locks.remove(&account_id);
+        }
+        log!("Self lock of {} set to {}", account_id, locked);
+    }
+
+    pub fn is_self_locked(&self, account_id: AccountId) -> bool {
+        self.self_locks.get(&account_id).unwrap_or(false)
+    }
+}
+
+impl Contract {
+    fn internal_locked_balance(&self, account_id: &AccountId) -> Balance {
+        self.balance_locks
+            .get(account_id)
+            .filter(|lock| env::block_timestamp() < lock.until_ns.0)
+            .map_or(0, |lock| lock.amount.0)
+    }
+
+    pub(crate) fn assert_not_self_locked(&self, account_id: &AccountId) {
+        require!(!self.self_locks.contains_key(account_id), ERR_SELF_LOCKED);
+    }
+
+    /// Panics if taking `amount` from `account_id` would dip into its locked balance.
+    pub(crate) fn assert_unlocked(&self, account_id: &AccountId, amount: Balance) {
+        let locked = self.internal_locked_balance(account_id);
+        if locked == 0 {
+            return;
+        }
+        let balance = self.token.accounts.get(account_id).unwrap_or(0);
+        require!(balance.saturating_sub(amount) >= locked, ERR_BALANCE_LOCKED);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Funds user1 with 1_000 tokens and locks 600 of them until 1_000.
+    fn setup_with_lock() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.lock_balance(user1(), 600.into(), 1_000.into());
+        (contract, context)
+    }
+
+    fn transfer_at(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        amount: Balance,
+        timestamp: u64,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.ft_transfer(user2(), amount.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_unlocked_part() {
+        let (mut contract, mut context) = setup_with_lock();
+        assert_eq!(contract.locked_balance_of(user1()).0, 600);
+
+        transfer_at(&mut contract, &mut context, 400, 500);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 600);
+        assert_eq!(contract.ft_balance_of(user2()).0, 400);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer exceeds the unlocked balance")]
+    fn test_transfer_locked_part() {
+        let (mut contract, mut context) = setup_with_lock();
+
+        transfer_at(&mut contract, &mut context, 401, 500);
+    }
+
+    #[test]
+    fn test_lock_expires() {
+        let (mut contract, mut context) = setup_with_lock();
+
+        transfer_at(&mut contract, &mut context, 1_000, 1_000);
+
+        assert_eq!(contract.locked_balance_of(user1()).0, 0);
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+    }
+
+    fn set_self_lock(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        account_id: AccountId,
+        locked: bool,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(account_id)
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_self_lock(locked);
+    }
+
+    #[test]
+    #[should_panic(expected = "Outgoing transfers are locked by the account")]
+    fn test_self_lock_blocks_transfers() {
+        let (mut contract, mut context) = setup_with_lock();
+        set_self_lock(&mut contract, &mut context, user1(), true);
+        assert!(contract.is_self_locked(user1()));
+
+        transfer_at(&mut contract, &mut context, 100, 0);
+    }
+
+    #[test]
+    fn test_self_unlock_allows_transfers() {
+        let (mut contract, mut context) = setup_with_lock();
+        set_self_lock(&mut contract, &mut context, user1(), true);
+        set_self_lock(&mut contract, &mut context, user1(), false);
+        assert!(!contract.is_self_locked(user1()));
+
+        transfer_at(&mut contract, &mut context, 100, 0);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_self_lock_requires_registration() {
+        let (mut contract, mut context) = setup();
+
+        set_self_lock(&mut contract, &mut context, user1(), true);
+    }
+
+    #[test]
+    fn test_self_lock_only_toggled_by_the_account() {
+        let (mut contract, mut context) = setup_with_lock();
+        set_self_lock(&mut contract, &mut context, user1(), true);
+
+        // the owner and other accounts only toggle their own lock
+        set_self_lock(&mut contract, &mut context, user2(), false);
+        set_self_lock(&mut contract, &mut context, owner(), false);
+
+        assert!(contract.is_self_locked(user1()));
+        assert!(!contract.is_self_locked(user2()));
+    }
+
+    #[test]
+    fn test_unlock_balance() {
+        let (mut contract, mut context) = setup_with_lock();
+        assert!(contract.unlock_balance(user1()));
+        assert!(contract.get_balance_lock(user1()).is_none());
+
+        transfer_at(&mut contract, &mut context, 1_000, 500);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't lock more than the balance")]
+    fn test_lock_above_balance() {
+        let (mut contract, _) = setup_with_lock();
+
+        contract.lock_balance(user1(), 1_001.into(), 1_000.into());
+    }
+}
@@ -0,0 +1,65 @@
+//! Checked arithmetic for the balances and totals the contract maintains itself, on top of
+//! the ones `FungibleToken` already checks. An overflow or underflow there is an accounting
+//! bug, so it panics with the operands instead of wrapping or saturating silently.
+
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::env;
+
+use crate::errors::{ERR_BALANCE_OVERFLOW, ERR_BALANCE_UNDERFLOW, ERR_SUPPLY_OVERFLOW};
+
+/// Returns `supply + amount` for a total of tokens, e.g. the total supply.
+pub(crate) fn checked_add_supply(supply: Balance, amount: Balance) -> Balance {
+    supply.checked_add(amount).unwrap_or_else(|| {
+        env::panic_str(&format!("{}: {} + {}", ERR_SUPPLY_OVERFLOW, supply, amount))
+    })
+}
+
+/// Returns `balance + amount` for a single balance or escrow.
+pub(crate) fn checked_add_balance(balance: Balance, amount: Balance) -> Balance {
+    balance.checked_add(amount).unwrap_or_else(|| {
+        env::panic_str(&format!(
+            "{}: {} + {}",
+            ERR_BALANCE_OVERFLOW, balance, amount
+        ))
+    })
+}
+
+/// Returns `balance - amount` for a balance, an escrow or a total.
+pub(crate) fn checked_sub_balance(balance: Balance, amount: Balance) -> Balance {
+    balance.checked_sub(amount).unwrap_or_else(|| {
+        env::panic_str(&format!(
+            "{}: {} - {}",
+            ERR_BALANCE_UNDERFLOW, balance, amount
+        ))
+    })
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_math_in_range() {
+        assert_eq!(checked_add_supply(u128::MAX - 1, 1), u128::MAX);
+        assert_eq!(checked_add_balance(2, 3), 5);
+        assert_eq!(checked_sub_balance(5, 5), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Total supply overflow: 340282366920938463463374607431768211455 + 1")]
+    fn test_checked_add_supply_overflow() {
+        checked_add_supply(u128::MAX, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Balance overflow: 340282366920938463463374607431768211455 + 2")]
+    fn test_checked_add_balance_overflow() {
+        checked_add_balance(u128::MAX, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Balance underflow: 3 - 4")]
+    fn test_checked_sub_balance_underflow() {
+        checked_sub_balance(3, 4);
+    }
+}
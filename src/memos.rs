@@ -0,0 +1,122 @@
+use near_sdk::{assert_one_yocto, near, AccountId};
+
+use crate::{Contract, ContractExt};
+
+/// Number of memos kept per account when the memo log is enabled.
+pub const RECENT_MEMOS_LIMIT: usize = 5;
+
+#[near]
+impl Contract {
+    /// Enables or disables recording of transfer memos. Disabled by default because every
+    /// recorded memo takes contract storage.
+    #[payable]
+    pub fn set_memo_log_enabled(&mut self, enabled: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.memo_log_enabled = enabled;
+    }
+
+    pub fn is_memo_log_enabled(&self) -> bool {
+        self.memo_log_enabled
+    }
+
+    /// Returns up to `RECENT_MEMOS_LIMIT` memos `account_id` attached to its transfers,
+    /// oldest first.
+    pub fn get_recent_memos(&self, account_id: AccountId) -> Vec<String> {
+        self.recent_memos.get(&account_id).unwrap_or_default()
+    }
+}
+
+impl Contract {
+    /// Appends `memo` to the sender's ring buffer of recent memos if the memo log is enabled.
+    pub(crate) fn record_memo(&mut self, sender_id: &AccountId, memo: Option<&str>) {
+        let Some(memo) = memo else {
+            return;
+        };
+        if !self.memo_log_enabled {
+            return;
+        }
+        let mut memos = self.recent_memos.get(sender_id).unwrap_or_default();
+        if memos.len() >= RECENT_MEMOS_LIMIT {
+            memos.remove(0);
+        }
+        memos.push(memo.to_string());
+        self.recent_memos.insert(sender_id, &memos);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1};
+
+    fn transfer_with_memo(contract: &mut Contract, context: &mut VMContextBuilder, memo: &str) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 10.into(), Some(memo.to_string()));
+    }
+
+    fn enable_memo_log(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_memo_log_enabled(true);
+    }
+
+    #[test]
+    fn test_memos_not_recorded_by_default() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        transfer_with_memo(&mut contract, &mut context, "invoice #1");
+
+        assert!(contract.get_recent_memos(owner()).is_empty());
+    }
+
+    #[test]
+    fn test_recent_memos_ring_buffer() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        enable_memo_log(&mut contract, &mut context);
+
+        for i in 0..RECENT_MEMOS_LIMIT + 2 {
+            transfer_with_memo(&mut contract, &mut context, &format!("invoice #{}", i));
+        }
+
+        let expected: Vec<String> = (2..RECENT_MEMOS_LIMIT + 2)
+            .map(|i| format!("invoice #{}", i))
+            .collect();
+        assert_eq!(contract.get_recent_memos(owner()), expected);
+        assert!(contract.get_recent_memos(user1()).is_empty());
+    }
+
+    #[test]
+    fn test_transfer_without_memo_is_not_recorded() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        enable_memo_log(&mut contract, &mut context);
+
+        contract.ft_transfer(user1(), 10.into(), None);
+
+        assert!(contract.get_recent_memos(owner()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_set_memo_log_enabled_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_memo_log_enabled(true);
+    }
+}
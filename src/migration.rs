@@ -0,0 +1,152 @@
+use near_contract_standards::fungible_token::events::FtMint;
+use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, log, near, require, AccountId};
+
+use crate::errors::{ERR_MIGRATION_ENDED, ERR_STORAGE_INITIALIZED, ERR_ZERO_AMOUNT};
+use crate::{Contract, ContractExt, StorageKey};
+
+#[near]
+impl Contract {
+    /// Creates the collections introduced after the first release under fresh storage keys.
+    /// Meant to be called once, right after the migration of a contract deployed with the
+    /// original layout and before any of the new features is used: calling it later would
+    /// drop the entries already stored in these collections, hence the one-time guard.
+    #[payable]
+    pub fn init_new_storage(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(!self.storage_initialized, ERR_STORAGE_INITIALIZED);
+        self.vesting = LookupMap::new(StorageKey::Vesting);
+        self.fee_exempt = UnorderedSet::new(StorageKey::FeeExempt);
+        self.recent_memos = LookupMap::new(StorageKey::RecentMemos);
+        self.minters = UnorderedSet::new(StorageKey::Minters);
+        self.excluded_accounts = UnorderedSet::new(StorageKey::ExcludedAccounts);
+        self.allowances = LookupMap::new(StorageKey::Allowances);
+        self.frozen_accounts = UnorderedSet::new(StorageKey::FrozenAccounts);
+        self.storage_initialized = true;
+        log!("New storage initialized");
+    }
+
+    pub fn is_storage_initialized(&self) -> bool {
+        self.storage_initialized
+    }
+
+    /// Credits `amount` new tokens to the registered `account_id` to carry over its balance
+    /// from the token being migrated from. Only possible until `end_migration` is called.
+    #[payable]
+    pub fn admin_credit(&mut self, account_id: AccountId, amount: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.migration_mode, ERR_MIGRATION_ENDED);
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        self.assert_valid_external_account(&account_id);
+        self.assert_within_max_supply(amount.0);
+        self.internal_credit(&account_id, amount.0);
+        self.record_minted(amount.0);
+        FtMint {
+            owner_id: &account_id,
+            amount,
+            memo: Some("migration"),
+        }
+        .emit();
+    }
+
+    /// Closes the migration for good: `admin_credit` always fails afterwards.
+    #[payable]
+    pub fn end_migration(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.migration_mode = false;
+        log!("Migration ended");
+    }
+
+    pub fn is_migration_mode(&self) -> bool {
+        self.migration_mode
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1};
+
+    fn init_new_storage(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.init_new_storage();
+    }
+
+    #[test]
+    fn test_init_new_storage() {
+        let (mut contract, mut context) = setup();
+        assert!(!contract.is_storage_initialized());
+
+        init_new_storage(&mut contract, &mut context);
+
+        assert!(contract.is_storage_initialized());
+        assert!(contract.add_minter(user1()));
+        assert!(contract.is_minter(user1()));
+    }
+
+    #[test]
+    fn test_admin_credit_during_migration() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        let total_supply = contract.ft_total_supply().0;
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert!(contract.is_migration_mode());
+        contract.admin_credit(user1(), 700.into());
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 700);
+        assert_eq!(contract.ft_total_supply().0, total_supply + 700);
+    }
+
+    #[test]
+    #[should_panic(expected = "Migration has ended")]
+    fn test_admin_credit_after_migration() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.admin_credit(user1(), 700.into());
+        contract.end_migration();
+        assert!(!contract.is_migration_mode());
+
+        contract.admin_credit(user1(), 700.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage already initialized")]
+    fn test_init_new_storage_twice() {
+        let (mut contract, mut context) = setup();
+
+        init_new_storage(&mut contract, &mut context);
+        init_new_storage(&mut contract, &mut context);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_init_new_storage_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.init_new_storage();
+    }
+}
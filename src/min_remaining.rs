@@ -0,0 +1,123 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::ERR_BELOW_MIN_REMAINING_BALANCE;
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Makes every transfer leave at least `min_remaining` with the sender, so accounts don't
+    /// drop to dust and unregister by accident, or removes the rule with `None`. An account
+    /// empties its balance on purpose with `ft_transfer_all`.
+    #[payable]
+    pub fn set_min_remaining_balance(&mut self, min_remaining: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Min remaining balance set to {:?}", min_remaining);
+        self.min_remaining_balance = min_remaining.map(|min_remaining| min_remaining.0);
+    }
+
+    pub fn get_min_remaining_balance(&self) -> Option<U128> {
+        self.min_remaining_balance.map(U128)
+    }
+
+    /// Transfers the whole balance of the caller to the registered `receiver_id`, regardless
+    /// of the min remaining balance. Returns the transferred amount.
+    #[payable]
+    pub fn ft_transfer_all(&mut self, receiver_id: AccountId, memo: Option<String>) -> U128 {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let amount = self.token.accounts.get(&sender_id).unwrap_or(0);
+        self.internal_transfer_without_min_remaining(&sender_id, &receiver_id, amount, memo);
+        amount.into()
+    }
+}
+
+impl Contract {
+    /// Panics if sending `amount` would leave `sender_id` with less than the min remaining
+    /// balance. A balance below `amount` is left to the transfer itself to reject, and a zero
+    /// amount never lowers the balance.
+    pub(crate) fn assert_min_remaining(&self, sender_id: &AccountId, amount: Balance) {
+        if let Some(min_remaining) = self.min_remaining_balance {
+            let balance = self.token.accounts.get(sender_id).unwrap_or(0);
+            require!(
+                amount == 0 || balance < amount || balance - amount >= min_remaining,
+                ERR_BELOW_MIN_REMAINING_BALANCE
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Funds user1 with 1_000 tokens and requires 10 to remain after transfers.
+    fn setup_with_min() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.set_min_remaining_balance(Some(10.into()));
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        (contract, context)
+    }
+
+    #[test]
+    fn test_transfer_leaving_min_remaining() {
+        let (mut contract, _) = setup_with_min();
+
+        contract.ft_transfer(user2(), 990.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer would leave less than the min remaining balance")]
+    fn test_transfer_below_min_remaining() {
+        let (mut contract, _) = setup_with_min();
+
+        contract.ft_transfer(user2(), 991.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer would leave less than the min remaining balance")]
+    fn test_transfer_of_whole_balance_below_min_remaining() {
+        let (mut contract, _) = setup_with_min();
+
+        contract.ft_transfer(user2(), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_all() {
+        let (mut contract, _) = setup_with_min();
+
+        assert_eq!(contract.ft_transfer_all(user2(), None).0, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount should be a positive number")]
+    fn test_transfer_all_empty_balance() {
+        let (mut contract, mut context) = setup_with_min();
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_all(user1(), None);
+    }
+}
@@ -0,0 +1,509 @@
+use near_contract_standards::fungible_token::events::FtMint;
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::{
+    ERR_EMPTY_BATCH, ERR_EMPTY_MINTER, ERR_MAX_SUPPLY_BELOW_TOTAL_SUPPLY, ERR_MAX_SUPPLY_EXCEEDED,
+    ERR_NOT_MINTER, ERR_SUPPLY_WARNING_TOO_HIGH, ERR_ZERO_AMOUNT,
+};
+use crate::events::emit_event;
+use crate::fees::{bps_of, FeeRounding, BPS_DENOMINATOR};
+use crate::supply::paginate_accounts;
+use crate::{Contract, ContractExt};
+
+/// Data of the `supply_warning` event emitted when minting crosses the warning threshold.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct SupplyWarning {
+    total_supply: U128,
+    threshold: U128,
+    max_supply: U128,
+}
+
+#[near]
+impl Contract {
+    /// Mints `amount` new tokens to the registered `account_id`. Minters only.
+    #[payable]
+    pub fn mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_minter();
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        self.assert_valid_external_account(&account_id);
+        self.assert_within_max_supply(amount.0);
+        self.assert_within_max_holding(&account_id, amount.0);
+        let previous_supply = self.token.total_supply;
+        self.internal_credit(&account_id, amount.0);
+        self.record_minted(amount.0);
+        self.check_supply_warning(previous_supply);
+        FtMint {
+            owner_id: &account_id,
+            amount,
+            memo: memo.as_deref(),
+        }
+        .emit();
+    }
+
+    /// Mints to several registered recipients at once and reports them in a single `FtMint`
+    /// event. The max supply is checked against the whole batch, so either every recipient
+    /// is credited or none is.
+    #[payable]
+    pub fn mint_batch(&mut self, recipients: Vec<(AccountId, U128)>) {
+        assert_one_yocto();
+        self.assert_minter();
+        require!(!recipients.is_empty(), ERR_EMPTY_BATCH);
+        self.assert_batch_size(recipients.len());
+        let total = recipients
+            .iter()
+            .try_fold(0u128, |total, (_, amount)| {
+                require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+                total.checked_add(amount.0)
+            })
+            .unwrap_or_else(|| env::panic_str(ERR_MAX_SUPPLY_EXCEEDED));
+        self.assert_within_max_supply(total);
+        let previous_supply = self.token.total_supply;
+        for (account_id, amount) in &recipients {
+            self.assert_valid_external_account(account_id);
+            self.assert_within_max_holding(account_id, amount.0);
+            self.internal_credit(account_id, amount.0);
+        }
+        self.record_minted(total);
+        self.check_supply_warning(previous_supply);
+        let events: Vec<FtMint> = recipients
+            .iter()
+            .map(|(account_id, amount)| FtMint {
+                owner_id: account_id,
+                amount: *amount,
+                memo: None,
+            })
+            .collect();
+        FtMint::emit_many(&events);
+    }
+
+    /// Caps the total supply reachable by minting, or removes the cap with `None`.
+    #[payable]
+    pub fn set_max_supply(&mut self, max_supply: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(max_supply) = max_supply {
+            require!(
+                max_supply.0 >= self.token.total_supply,
+                ERR_MAX_SUPPLY_BELOW_TOTAL_SUPPLY
+            );
+        }
+        self.max_supply = max_supply.map(|max_supply| max_supply.0);
+    }
+
+    pub fn get_max_supply(&self) -> Option<U128> {
+        self.max_supply.map(U128)
+    }
+
+    /// Sets the share of the max supply, in basis points, past which minting emits a
+    /// `supply_warning` event, or removes it with `None`. The warning never blocks minting.
+    #[payable]
+    pub fn set_supply_warning(&mut self, warning_bps: Option<u16>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(warning_bps) = warning_bps {
+            require!(
+                u128::from(warning_bps) <= BPS_DENOMINATOR,
+                ERR_SUPPLY_WARNING_TOO_HIGH
+            );
+        }
+        self.supply_warning_bps = warning_bps;
+    }
+
+    pub fn get_supply_warning(&self) -> Option<u16> {
+        self.supply_warning_bps
+    }
+
+    #[payable]
+    pub fn add_minter(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_valid_external_account(&account_id);
+        self.minters.insert(&account_id)
+    }
+
+    #[payable]
+    pub fn remove_minter(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.minters.remove(&account_id)
+    }
+
+    /// Replaces the whole minter set at once, e.g. when rotating signing infrastructure.
+    /// Duplicated entries are stored once.
+    #[payable]
+    pub fn set_minters(&mut self, minters: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            minters.iter().all(|minter| !minter.as_str().is_empty()),
+            ERR_EMPTY_MINTER
+        );
+        for minter in &minters {
+            self.assert_valid_external_account(minter);
+        }
+        self.minters.clear();
+        for minter in &minters {
+            self.minters.insert(minter);
+        }
+        log!("Minters set to {:?}", self.minters.to_vec());
+    }
+
+    pub fn get_minters(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AccountId> {
+        paginate_accounts(&self.minters, from_index, limit)
+    }
+
+    pub fn is_minter(&self, account_id: AccountId) -> bool {
+        self.minters.contains(&account_id)
+    }
+
+    /// Returns the block timestamp set in `new` before which minting is locked, if any.
+    pub fn get_mint_unlock_ns(&self) -> Option<U64> {
+        self.mint_unlock_ns.map(U64)
+    }
+}
+
+impl Contract {
+    /// Panics unless called by a minter of a contract that wasn't finalized nor paused, with
+    /// minting not paused either, once the mint lock window is over.
+    pub(crate) fn assert_minter(&self) {
+        self.assert_not_finalized();
+        self.assert_not_paused();
+        self.assert_minting_not_paused();
+        if let Some(mint_unlock_ns) = self.mint_unlock_ns {
+            if env::block_timestamp() < mint_unlock_ns {
+                env::panic_str(&format!("Minting locked until {}", mint_unlock_ns));
+            }
+        }
+        require!(
+            self.minters.contains(&env::predecessor_account_id()),
+            ERR_NOT_MINTER
+        );
+    }
+
+    /// Emits a `supply_warning` event if the mint that raised the total supply from
+    /// `previous_supply` crossed the warning threshold.
+    fn check_supply_warning(&self, previous_supply: Balance) {
+        let (Some(max_supply), Some(warning_bps)) = (self.max_supply, self.supply_warning_bps)
+        else {
+            return;
+        };
+        let threshold = bps_of(max_supply, warning_bps, FeeRounding::Down);
+        let total_supply = self.token.total_supply;
+        if previous_supply < threshold && total_supply >= threshold {
+            emit_event(
+                "supply_warning",
+                SupplyWarning {
+                    total_supply: total_supply.into(),
+                    threshold: threshold.into(),
+                    max_supply: max_supply.into(),
+                },
+            );
+        }
+    }
+
+    /// Panics if minting `amount` more tokens would exceed the max supply.
+    pub(crate) fn assert_within_max_supply(&self, amount: Balance) {
+        let Some(max_supply) = self.max_supply else {
+            return;
+        };
+        require!(
+            self.token
+                .total_supply
+                .checked_add(amount)
+                .is_some_and(|total_supply| total_supply <= max_supply),
+            ERR_MAX_SUPPLY_EXCEEDED
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{metadata, owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    fn set_minters(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        minters: Vec<AccountId>,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_minters(minters);
+    }
+
+    fn mint_as(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        minter_id: AccountId,
+        amount: u128,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(minter_id)
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.mint(owner(), amount.into(), None);
+    }
+
+    #[test]
+    fn test_mint() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        set_minters(&mut contract, &mut context, vec![user2()]);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.mint(user1(), 1_000.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minter's method")]
+    fn test_mint_only_minter() {
+        let (mut contract, mut context) = setup();
+
+        mint_as(&mut contract, &mut context, owner(), 1_000);
+    }
+
+    #[test]
+    fn test_add_and_remove_minter() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert!(contract.add_minter(user1()));
+        assert!(!contract.add_minter(user1()));
+        assert!(contract.is_minter(user1()));
+        assert!(contract.remove_minter(user1()));
+        assert!(!contract.is_minter(user1()));
+    }
+
+    #[test]
+    fn test_set_minters_replaces_whole_set() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1(), user2()]);
+
+        let new_minters = vec![accounts(4), accounts(5), owner()];
+        set_minters(&mut contract, &mut context, new_minters.clone());
+
+        let mut minters = contract.get_minters(None, None);
+        minters.sort();
+        let mut expected = new_minters;
+        expected.sort();
+        assert_eq!(minters, expected);
+        assert!(!contract.is_minter(user1()));
+        assert!(!contract.is_minter(user2()));
+
+        mint_as(&mut contract, &mut context, accounts(4), 1_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minter's method")]
+    fn test_set_minters_revokes_old_minters() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1(), user2()]);
+        set_minters(&mut contract, &mut context, vec![accounts(4)]);
+
+        mint_as(&mut contract, &mut context, user1(), 1_000);
+    }
+
+    #[test]
+    fn test_set_minters_dedupes() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1(), user1(), user2()]);
+
+        assert_eq!(contract.get_minters(None, None).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_set_minters_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_minters(vec![user1()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting locked until 1000")]
+    fn test_mint_before_unlock() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new(
+            owner(),
+            TOTAL_SUPPLY.into(),
+            metadata(),
+            Some(1_000.into()),
+            None,
+        );
+        set_minters(&mut contract, &mut context, vec![user1()]);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(999)
+            .build());
+        contract.mint(owner(), 1_000.into(), None);
+    }
+
+    #[test]
+    fn test_mint_after_unlock() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = Contract::new(
+            owner(),
+            TOTAL_SUPPLY.into(),
+            metadata(),
+            Some(1_000.into()),
+            None,
+        );
+        set_minters(&mut contract, &mut context, vec![user1()]);
+        assert_eq!(contract.get_mint_unlock_ns(), Some(1_000.into()));
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(1_000)
+            .build());
+        contract.mint(owner(), 1_000.into(), None);
+
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
+
+    #[test]
+    fn test_mint_batch() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        set_minters(&mut contract, &mut context, vec![accounts(4)]);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.mint_batch(vec![(user1(), 1_000.into()), (user2(), 2_000.into())]);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(user2()).0, 2_000);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 3_000);
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#""event":"ft_mint""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_mint_batch_unregistered_recipient() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        set_minters(&mut contract, &mut context, vec![accounts(4)]);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.mint_batch(vec![(user1(), 1_000.into()), (user2(), 2_000.into())]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply exceeded")]
+    fn test_mint_batch_exceeds_max_supply() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        set_minters(&mut contract, &mut context, vec![accounts(4)]);
+        contract.set_max_supply(Some((TOTAL_SUPPLY + 2_000).into()));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        // each mint fits under the cap on its own, the batch doesn't
+        contract.mint_batch(vec![(user1(), 1_500.into()), (user2(), 1_500.into())]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply exceeded")]
+    fn test_mint_exceeds_max_supply() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1()]);
+        contract.set_max_supply(Some(TOTAL_SUPPLY.into()));
+
+        mint_as(&mut contract, &mut context, user1(), 1);
+    }
+
+    fn supply_warnings() -> usize {
+        get_logs()
+            .iter()
+            .filter(|log| log.contains(r#""event":"supply_warning""#))
+            .count()
+    }
+
+    #[test]
+    fn test_supply_warning_fires_once_when_crossed() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1()]);
+        contract.set_max_supply(Some((TOTAL_SUPPLY * 2).into()));
+        contract.set_supply_warning(Some(7_500));
+        assert_eq!(contract.get_supply_warning(), Some(7_500));
+
+        mint_as(&mut contract, &mut context, user1(), TOTAL_SUPPLY / 4);
+        assert_eq!(supply_warnings(), 0);
+
+        mint_as(&mut contract, &mut context, user1(), TOTAL_SUPPLY / 4);
+        assert_eq!(supply_warnings(), 1);
+        let expected = format!(
+            r#""total_supply":"{}","threshold":"{}""#,
+            TOTAL_SUPPLY * 3 / 2,
+            TOTAL_SUPPLY * 3 / 2
+        );
+        assert!(get_logs().iter().any(|log| log.contains(&expected)));
+
+        // already above the threshold: minting keeps working without further warnings
+        mint_as(&mut contract, &mut context, user1(), 1);
+        assert_eq!(supply_warnings(), 0);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY * 3 / 2 + 1);
+    }
+
+    #[test]
+    fn test_supply_warning_with_max_supply_at_balance_limit() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1()]);
+        contract.set_max_supply(Some(u128::MAX.into()));
+        contract.set_supply_warning(Some(9_000));
+
+        mint_as(&mut contract, &mut context, user1(), 1);
+
+        assert_eq!(supply_warnings(), 0);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract finalized")]
+    fn test_mint_fails_after_finalize() {
+        let (mut contract, mut context) = setup();
+        set_minters(&mut contract, &mut context, vec![user1()]);
+        contract.finalize();
+
+        mint_as(&mut contract, &mut context, user1(), 1_000);
+    }
+}
@@ -0,0 +1,130 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, ext_contract, log, near, AccountId, Gas};
+
+use crate::{Contract, ContractExt};
+
+/// Gas attached to the observer notification. It is fire-and-forget, so it is kept low.
+const GAS_FOR_TRANSFER_NOTIFICATION: Gas = Gas::from_tgas(5);
+
+/// Interface of the contract observing transfers, e.g. for analytics.
+#[allow(dead_code)]
+#[ext_contract(ext_transfer_observer)]
+pub trait TransferObserver {
+    fn on_ft_transfer(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: U128);
+}
+
+#[near]
+impl Contract {
+    /// Sets the contract notified after each transfer, or removes it with `None`.
+    #[payable]
+    pub fn set_transfer_observer(&mut self, observer_id: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Transfer observer set to {:?}", observer_id);
+        self.transfer_observer = observer_id;
+    }
+
+    pub fn get_transfer_observer(&self) -> Option<AccountId> {
+        self.transfer_observer.clone()
+    }
+}
+
+impl Contract {
+    /// Schedules the observer notification for a transfer, if an observer is set. The result
+    /// of the call is ignored, so a failing observer never affects the transfer.
+    pub(crate) fn notify_transfer_observer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) {
+        if let Some(observer_id) = &self.transfer_observer {
+            ext_transfer_observer::ext(observer_id.clone())
+                .with_static_gas(GAS_FOR_TRANSFER_NOTIFICATION)
+                .with_unused_gas_weight(0)
+                .on_ft_transfer(sender_id.clone(), receiver_id.clone(), amount.into());
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1};
+
+    fn observer() -> AccountId {
+        accounts(5)
+    }
+
+    fn transfer(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 100.into(), None);
+    }
+
+    /// Returns the arguments of the `on_ft_transfer` calls scheduled to the observer.
+    fn observer_notifications() -> Vec<Vec<u8>> {
+        get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == observer())
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                MockAction::FunctionCallWeight {
+                    method_name, args, ..
+                } if method_name == b"on_ft_transfer" => Some(args),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_observer_notified_when_set() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_observer(Some(observer()));
+        transfer(&mut contract, &mut context);
+
+        let notifications = observer_notifications();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(
+            String::from_utf8(notifications[0].clone()).unwrap(),
+            format!(
+                r#"{{"sender_id":"{}","receiver_id":"{}","amount":"100"}}"#,
+                owner(),
+                user1()
+            )
+        );
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    fn test_observer_not_notified_when_unset() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        transfer(&mut contract, &mut context);
+        assert!(observer_notifications().is_empty());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_observer(Some(observer()));
+        contract.set_transfer_observer(None);
+        transfer(&mut contract, &mut context);
+        assert!(observer_notifications().is_empty());
+    }
+}
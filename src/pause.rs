@@ -0,0 +1,223 @@
+use near_sdk::{assert_one_yocto, log, near, require, AccountId};
+
+use crate::errors::{ERR_EMPTY_OWNER, ERR_MINTING_PAUSED, ERR_PAUSED};
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Pauses or resumes transfers and minting. The optional `reason` is shown to users through
+    /// `pause_info` while the contract is paused and is cleared on unpause.
+    #[payable]
+    pub fn set_paused(&mut self, paused: bool, reason: Option<String>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.paused = paused;
+        self.pause_reason = if paused { reason } else { None };
+        log!(
+            "Contract {} by {}",
+            if paused { "paused" } else { "unpaused" },
+            self.owner_id
+        );
+    }
+
+    /// Pauses the contract and hands it over to `new_owner` in one call, so the incoming
+    /// owner takes over a contract nothing can move in. The previous pause reason is kept.
+    #[payable]
+    pub fn emergency_handover(&mut self, new_owner: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(!new_owner.as_str().is_empty(), ERR_EMPTY_OWNER);
+        self.assert_valid_external_account(&new_owner);
+        self.paused = true;
+        log!(
+            "Contract paused and handed over from {} to {}",
+            self.owner_id,
+            new_owner
+        );
+        self.owner_id = new_owner;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns whether the contract is paused and why.
+    pub fn pause_info(&self) -> (bool, Option<String>) {
+        (self.paused, self.pause_reason.clone())
+    }
+
+    /// Pauses or resumes minting only, leaving transfers untouched.
+    #[payable]
+    pub fn set_minting_paused(&mut self, paused: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.minting_paused = paused;
+        log!(
+            "Minting {} by {}",
+            if paused { "paused" } else { "unpaused" },
+            self.owner_id
+        );
+    }
+
+    pub fn is_minting_paused(&self) -> bool {
+        self.minting_paused
+    }
+}
+
+impl Contract {
+    /// Panics while the contract is paused or a rebase spanning several calls is unfinished,
+    /// as balances can't move until every holder was scaled.
+    pub(crate) fn assert_not_paused(&self) {
+        require!(!self.paused, ERR_PAUSED);
+        self.assert_no_rebase_in_progress();
+    }
+
+    pub(crate) fn assert_minting_not_paused(&self) {
+        require!(!self.minting_paused, ERR_MINTING_PAUSED);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    fn set_paused(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        paused: bool,
+        reason: Option<&str>,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_paused(paused, reason.map(str::to_string));
+    }
+
+    #[test]
+    fn test_pause_with_reason() {
+        let (mut contract, mut context) = setup();
+        assert_eq!(contract.pause_info(), (false, None));
+
+        set_paused(&mut contract, &mut context, true, Some("Bridge incident"));
+
+        assert!(contract.is_paused());
+        assert_eq!(
+            contract.pause_info(),
+            (true, Some("Bridge incident".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unpause_clears_reason() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        set_paused(&mut contract, &mut context, true, Some("Bridge incident"));
+
+        set_paused(&mut contract, &mut context, false, Some("ignored"));
+
+        assert_eq!(contract.pause_info(), (false, None));
+        contract.ft_transfer(user1(), 100.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract paused")]
+    fn test_transfer_fails_when_paused() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        set_paused(&mut contract, &mut context, true, None);
+
+        contract.ft_transfer(user1(), 100.into(), None);
+    }
+
+    fn set_minting_paused(contract: &mut Contract, context: &mut VMContextBuilder, paused: bool) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_minting_paused(paused);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting paused")]
+    fn test_mint_fails_when_minting_paused() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.add_minter(user2());
+        set_minting_paused(&mut contract, &mut context, true);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.mint(user1(), 100.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_while_minting_paused() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        set_minting_paused(&mut contract, &mut context, true);
+
+        assert!(contract.is_minting_paused());
+        assert!(!contract.is_paused());
+        contract.ft_transfer(user1(), 100.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+
+        set_minting_paused(&mut contract, &mut context, false);
+        assert!(!contract.is_minting_paused());
+    }
+
+    #[test]
+    fn test_emergency_handover() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.emergency_handover(user2());
+
+        assert!(contract.is_paused());
+        assert_eq!(contract.get_config().owner_id, user2());
+
+        // the new owner can resume the contract
+        testing_env!(context.predecessor_account_id(user2()).build());
+        contract.set_paused(false, None);
+        assert!(!contract.is_paused());
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_emergency_handover_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.emergency_handover(user1());
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_set_paused_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_paused(true, None);
+    }
+}
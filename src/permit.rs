@@ -0,0 +1,408 @@
+// The permit methods take the signed transfer fields along with the signature and key.
+#![allow(clippy::too_many_arguments)]
+
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, CurveType, PublicKey};
+
+use crate::errors::{
+    ERR_INVALID_NONCE, ERR_INVALID_SIGNATURE, ERR_NOT_ED25519_KEY, ERR_PERMIT_EXPIRED,
+    ERR_SELF_APPROVAL, ERR_UNKNOWN_PERMIT_KEY,
+};
+use crate::{Contract, ContractExt};
+
+/// Returns the message an owner signs to authorize a transfer of its tokens by a relayer.
+/// The token account is part of the message so a signature can't be replayed on another
+/// token.
+pub fn transfer_permit_message(
+    owner_id: &AccountId,
+    receiver_id: &AccountId,
+    amount: U128,
+    nonce: U64,
+) -> String {
+    format!(
+        "{}:ft_transfer:{}:{}:{}:{}",
+        env::current_account_id(),
+        owner_id,
+        receiver_id,
+        amount.0,
+        nonce.0
+    )
+}
+
+/// Returns the message an owner signs to let a relayer set the allowance of `spender_id` to
+/// `amount`, valid until the block timestamp `deadline_ns`. It shares the nonce sequence of
+/// the transfer permits.
+pub fn approve_permit_message(
+    owner_id: &AccountId,
+    spender_id: &AccountId,
+    amount: U128,
+    nonce: U64,
+    deadline_ns: U64,
+) -> String {
+    format!(
+        "{}:ft_approve:{}:{}:{}:{}:{}",
+        env::current_account_id(),
+        owner_id,
+        spender_id,
+        amount.0,
+        nonce.0,
+        deadline_ns.0
+    )
+}
+
+#[near]
+impl Contract {
+    /// Sets the ed25519 key whose signatures authorize transfers of the caller's tokens, or
+    /// removes it with `None`.
+    #[payable]
+    pub fn set_permit_key(&mut self, public_key: Option<PublicKey>) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        match public_key {
+            Some(public_key) => {
+                require!(
+                    public_key.curve_type() == CurveType::ED25519,
+                    ERR_NOT_ED25519_KEY
+                );
+                self.permit_keys.insert(&account_id, &public_key);
+            }
+            None => {
+                self.permit_keys.remove(&account_id);
+            }
+        }
+    }
+
+    pub fn get_permit_key(&self, account_id: AccountId) -> Option<PublicKey> {
+        self.permit_keys.get(&account_id)
+    }
+
+    /// Returns the nonce the next permit of `account_id` must carry.
+    pub fn get_permit_nonce(&self, account_id: AccountId) -> U64 {
+        self.permit_nonces.get(&account_id).unwrap_or(0).into()
+    }
+
+    /// Transfers `amount` of `owner_id`'s tokens to `receiver_id` on behalf of the owner, who
+    /// signed `transfer_permit_message` with its permit key. Meant to be submitted by relayers.
+    pub fn ft_transfer_with_signature(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        nonce: U64,
+        signature: Base64VecU8,
+        public_key: PublicKey,
+        memo: Option<String>,
+    ) {
+        self.check_transfer_permit(
+            &owner_id,
+            &receiver_id,
+            amount,
+            nonce,
+            &signature,
+            &public_key,
+        )
+        .unwrap_or_else(|err| env::panic_str(err));
+        self.permit_nonces.insert(&owner_id, &(nonce.0 + 1));
+        log!(
+            "Permit {} of {} used by {}",
+            nonce.0,
+            owner_id,
+            env::predecessor_account_id()
+        );
+        self.internal_transfer(&owner_id, &receiver_id, amount.0, memo);
+    }
+
+    /// Sets the allowance of `spender_id` over `owner_id`'s tokens to `amount` on behalf of
+    /// the owner, who signed `approve_permit_message` with its permit key. Like `ft_approve`,
+    /// it replaces any previous allowance and its expiry.
+    pub fn ft_approve_with_signature(
+        &mut self,
+        owner_id: AccountId,
+        spender_id: AccountId,
+        amount: U128,
+        nonce: U64,
+        deadline_ns: U64,
+        signature: Base64VecU8,
+        public_key: PublicKey,
+    ) {
+        require!(env::block_timestamp() <= deadline_ns.0, ERR_PERMIT_EXPIRED);
+        require!(owner_id != spender_id, ERR_SELF_APPROVAL);
+        let message = approve_permit_message(&owner_id, &spender_id, amount, nonce, deadline_ns);
+        self.check_permit(&owner_id, nonce, &message, &signature, &public_key)
+            .unwrap_or_else(|err| env::panic_str(err));
+        self.assert_storage_funded(&owner_id);
+        self.permit_nonces.insert(&owner_id, &(nonce.0 + 1));
+        log!(
+            "Approval permit {} of {} used by {}",
+            nonce.0,
+            owner_id,
+            env::predecessor_account_id()
+        );
+        self.internal_set_allowance(&owner_id, &spender_id, amount.0);
+        self.allowance_expiries.remove(&(owner_id, spender_id));
+    }
+
+    /// Runs the same checks as `ft_transfer_with_signature` without executing anything, so
+    /// wallets and relayers can validate a permit before broadcasting it.
+    pub fn verify_transfer_signature(
+        &self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        nonce: U64,
+        signature: Base64VecU8,
+        public_key: PublicKey,
+    ) -> bool {
+        self.check_transfer_permit(
+            &owner_id,
+            &receiver_id,
+            amount,
+            nonce,
+            &signature,
+            &public_key,
+        )
+        .is_ok()
+    }
+}
+
+impl Contract {
+    fn check_transfer_permit(
+        &self,
+        owner_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: U128,
+        nonce: U64,
+        signature: &Base64VecU8,
+        public_key: &PublicKey,
+    ) -> Result<(), &'static str> {
+        let message = transfer_permit_message(owner_id, receiver_id, amount, nonce);
+        self.check_permit(owner_id, nonce, &message, signature, public_key)
+    }
+
+    /// Checks that `message` is signed by the permit key of `owner_id` and carries its next
+    /// nonce.
+    fn check_permit(
+        &self,
+        owner_id: &AccountId,
+        nonce: U64,
+        message: &str,
+        signature: &Base64VecU8,
+        public_key: &PublicKey,
+    ) -> Result<(), &'static str> {
+        if self.permit_keys.get(owner_id).as_ref() != Some(public_key) {
+            return Err(ERR_UNKNOWN_PERMIT_KEY);
+        }
+        if self.permit_nonces.get(owner_id).unwrap_or(0) != nonce.0 {
+            return Err(ERR_INVALID_NONCE);
+        }
+        let (Ok(signature), Ok(key)) = (
+            <&[u8; 64]>::try_from(signature.0.as_slice()),
+            <&[u8; 32]>::try_from(&public_key.as_bytes()[1..]),
+        ) else {
+            return Err(ERR_INVALID_SIGNATURE);
+        };
+        if !env::ed25519_verify(signature, message.as_bytes(), key) {
+            return Err(ERR_INVALID_SIGNATURE);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    pub(crate) fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    pub(crate) fn permit_public_key() -> PublicKey {
+        PublicKey::from_parts(
+            CurveType::ED25519,
+            signing_key().verifying_key().to_bytes().to_vec(),
+        )
+        .unwrap()
+    }
+
+    fn sign(message: &str) -> Base64VecU8 {
+        signing_key()
+            .sign(message.as_bytes())
+            .to_bytes()
+            .to_vec()
+            .into()
+    }
+
+    /// Funds user1 with 1_000 tokens and registers its permit key.
+    fn setup_permit(contract: &mut Contract, context: &mut VMContextBuilder) {
+        register_user(contract, context, user1());
+        register_user(contract, context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_permit_key(Some(permit_public_key()));
+        // a relayer submits the permits
+        testing_env!(context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(NearToken::from_near(0))
+            .build());
+    }
+
+    fn permit(nonce: u64) -> Base64VecU8 {
+        sign(&transfer_permit_message(
+            &user1(),
+            &user2(),
+            100.into(),
+            nonce.into(),
+        ))
+    }
+
+    #[test]
+    fn test_transfer_with_signature() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+
+        contract.ft_transfer_with_signature(
+            user1(),
+            user2(),
+            100.into(),
+            0.into(),
+            permit(0),
+            permit_public_key(),
+            None,
+        );
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 100);
+        assert_eq!(contract.get_permit_nonce(user1()).0, 1);
+    }
+
+    fn approve_with_signature(contract: &mut Contract, nonce: u64, deadline_ns: u64) {
+        let signature = sign(&approve_permit_message(
+            &user1(),
+            &user2(),
+            300.into(),
+            nonce.into(),
+            deadline_ns.into(),
+        ));
+        contract.ft_approve_with_signature(
+            user1(),
+            user2(),
+            300.into(),
+            nonce.into(),
+            deadline_ns.into(),
+            signature,
+            permit_public_key(),
+        );
+    }
+
+    #[test]
+    fn test_approve_with_signature() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+
+        approve_with_signature(&mut contract, 0, 1_000);
+
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 300);
+        assert_eq!(contract.get_permit_nonce(user1()).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Permit deadline passed")]
+    fn test_approve_with_signature_after_deadline() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+        testing_env!(context.block_timestamp(1_001).build());
+
+        approve_with_signature(&mut contract, 0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid permit nonce")]
+    fn test_approve_with_replayed_signature() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+
+        approve_with_signature(&mut contract, 0, 1_000);
+        approve_with_signature(&mut contract, 0, 1_000);
+    }
+
+    #[test]
+    fn test_verify_valid_signature() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+
+        assert!(contract.verify_transfer_signature(
+            user1(),
+            user2(),
+            100.into(),
+            0.into(),
+            permit(0),
+            permit_public_key(),
+        ));
+        // verifying doesn't consume the nonce
+        assert_eq!(contract.get_permit_nonce(user1()).0, 0);
+        // the signature covers the amount
+        assert!(!contract.verify_transfer_signature(
+            user1(),
+            user2(),
+            101.into(),
+            0.into(),
+            permit(0),
+            permit_public_key(),
+        ));
+    }
+
+    #[test]
+    fn test_verify_stale_nonce() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+        contract.ft_transfer_with_signature(
+            user1(),
+            user2(),
+            100.into(),
+            0.into(),
+            permit(0),
+            permit_public_key(),
+            None,
+        );
+
+        assert!(!contract.verify_transfer_signature(
+            user1(),
+            user2(),
+            100.into(),
+            0.into(),
+            permit(0),
+            permit_public_key(),
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid permit nonce")]
+    fn test_transfer_with_replayed_signature() {
+        let (mut contract, mut context) = setup();
+        setup_permit(&mut contract, &mut context);
+
+        for _ in 0..2 {
+            contract.ft_transfer_with_signature(
+                user1(),
+                user2(),
+                100.into(),
+                0.into(),
+                permit(0),
+                permit_public_key(),
+                None,
+            );
+        }
+    }
+}
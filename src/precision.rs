@@ -0,0 +1,102 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadataProvider;
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::{assert_one_yocto, near, require};
+
+use crate::errors::{ERR_AMOUNT_PRECISION, ERR_PRECISION_TOO_HIGH};
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Limits transfer amounts to `precision` decimal places of a whole token, e.g. 2 for
+    /// cents, or removes the limit with `None`.
+    #[payable]
+    pub fn set_transfer_precision(&mut self, precision: Option<u8>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(precision) = precision {
+            require!(
+                precision <= self.ft_metadata().decimals,
+                ERR_PRECISION_TOO_HIGH
+            );
+        }
+        self.transfer_precision = precision;
+    }
+
+    pub fn get_transfer_precision(&self) -> Option<u8> {
+        self.transfer_precision
+    }
+}
+
+impl Contract {
+    /// Panics if `amount` isn't a multiple of the smallest unit allowed by the transfer
+    /// precision.
+    pub(crate) fn assert_transfer_precision(&self, amount: Balance) {
+        let Some(precision) = self.transfer_precision else {
+            return;
+        };
+        let unit = 10u128.pow(u32::from(self.ft_metadata().decimals - precision));
+        require!(amount % unit == 0, ERR_AMOUNT_PRECISION);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1};
+
+    /// Unit of a transfer with 2 decimal places, for 24 decimals.
+    const CENT: Balance = 10u128.pow(22);
+
+    fn setup_with_cents() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        contract
+            .token
+            .internal_deposit(&owner(), 1_000 * 100 * CENT);
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_transfer_precision(Some(2));
+        (contract, context)
+    }
+
+    #[test]
+    fn test_transfer_aligned_amount() {
+        let (mut contract, _) = setup_with_cents();
+
+        contract.ft_transfer(user1(), (125 * CENT).into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 125 * CENT);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer amount has more precision than allowed")]
+    fn test_transfer_misaligned_amount() {
+        let (mut contract, _) = setup_with_cents();
+
+        contract.ft_transfer(user1(), (125 * CENT + 1).into(), None);
+    }
+
+    #[test]
+    fn test_transfer_any_amount_without_precision() {
+        let (mut contract, _) = setup_with_cents();
+        contract.set_transfer_precision(None);
+
+        contract.ft_transfer(user1(), 1.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer precision can't exceed the token decimals")]
+    fn test_set_transfer_precision_above_decimals() {
+        let (mut contract, _) = setup_with_cents();
+
+        contract.set_transfer_precision(Some(25));
+    }
+}
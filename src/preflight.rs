@@ -0,0 +1,430 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadataProvider;
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, AccountId};
+
+use crate::receiver_policy::UnregisteredReceiverPolicy;
+use crate::{Contract, ContractExt};
+
+/// Outcome of `check_transfer`. Every variant but `Ok` names the guard that would make the
+/// transfer panic, so front ends can show a localized message before sending it.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub enum TransferCheck {
+    Ok,
+    SelfTransfer,
+    ZeroAmount,
+    Paused,
+    SenderFrozen,
+    SenderSelfLocked,
+    ReceiverNotAllowed,
+    InvalidPrecision,
+    ReceiverUnregistered,
+    InsufficientBalance,
+    BalanceLocked,
+    BelowMinRemainingBalance,
+    VolumeCapReached,
+    ExceedsMaxHolding,
+}
+
+/// Outcome of `can_mint`: `Ok` or the reason minting would panic now.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub enum MintCheck {
+    Ok,
+    Finalized,
+    Paused,
+    MintingPaused,
+    MintLocked,
+    ZeroAmount,
+    ExceedsMaxSupply,
+}
+
+/// Dynamic effects of a transfer, returned by `preview_transfer_effects`.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub struct TransferEffects {
+    /// Transfer fee taken from the amount.
+    pub fee: U128,
+    /// Transfer burn taken from the amount.
+    pub burn: U128,
+    /// Amount the receiver gets.
+    pub credited: U128,
+    /// Volume left under the cap of the current epoch before the transfer, `None` without a
+    /// cap.
+    pub remaining_epoch_volume: Option<U128>,
+    /// Whether the transfer would exceed the volume cap of the current epoch.
+    pub crosses_volume_cap: bool,
+}
+
+#[near]
+impl Contract {
+    /// Previews the fee, burn and volume cap effects of a transfer from `sender_id` to
+    /// `receiver_id` under the current settings, so UIs can warn users before sending it.
+    /// Guards are not checked, see `check_transfer`.
+    pub fn preview_transfer_effects(
+        &self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> TransferEffects {
+        let splits = self.compute_splits(&sender_id, &receiver_id, amount.0);
+        TransferEffects {
+            fee: splits.to_collector.into(),
+            burn: splits.burned.into(),
+            credited: splits.to_receiver.into(),
+            remaining_epoch_volume: self
+                .volume_cap_per_epoch
+                .map(|cap| cap.saturating_sub(self.get_epoch_volume().0).into()),
+            crosses_volume_cap: self.exceeds_volume_cap(amount.0),
+        }
+    }
+
+    /// Returns whether a minter could mint `amount` right now, or the first reason it
+    /// couldn't. Doesn't check that the caller is a minter.
+    pub fn can_mint(&self, amount: U128) -> MintCheck {
+        if self.finalized {
+            return MintCheck::Finalized;
+        }
+        if self.paused || self.rebase_progress.is_some() {
+            return MintCheck::Paused;
+        }
+        if self.minting_paused {
+            return MintCheck::MintingPaused;
+        }
+        if self
+            .mint_unlock_ns
+            .is_some_and(|mint_unlock_ns| env::block_timestamp() < mint_unlock_ns)
+        {
+            return MintCheck::MintLocked;
+        }
+        if amount.0 == 0 {
+            return MintCheck::ZeroAmount;
+        }
+        if self.max_supply.is_some_and(|max_supply| {
+            self.token
+                .total_supply
+                .checked_add(amount.0)
+                .is_none_or(|total_supply| total_supply > max_supply)
+        }) {
+            return MintCheck::ExceedsMaxSupply;
+        }
+        MintCheck::Ok
+    }
+
+    /// Estimates, from local state only, how much of `amount` an `ft_transfer_call` to
+    /// `receiver_id` would leave with the sender. A receiver that isn't registered with this
+    /// token makes the whole call fail, so the full amount stays with the sender. For a
+    /// registered receiver the refund is decided by its `ft_on_transfer`, which a view can't
+    /// call, so 0 is returned.
+    pub fn expected_refund_if_unregistered(&self, receiver_id: AccountId, amount: U128) -> U128 {
+        if self.token.accounts.contains_key(&receiver_id) {
+            U128(0)
+        } else {
+            amount
+        }
+    }
+
+    /// Runs the guards of `ft_transfer` without moving any tokens and returns the first that
+    /// fails. The guards are checked in a fixed order that doesn't always match the one of
+    /// `ft_transfer`, so when several fail the transfer may panic with another of them. A
+    /// rebase in progress is reported as `Paused`, as in `can_mint`.
+    pub fn check_transfer(
+        &self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> TransferCheck {
+        let credited = self
+            .compute_splits(&sender_id, &receiver_id, amount.0)
+            .to_receiver;
+        self.first_failed_transfer_guard(
+            &sender_id,
+            &receiver_id,
+            amount.0,
+            credited,
+            self.unregistered_receiver_policy,
+        )
+    }
+}
+
+impl Contract {
+    /// Returns the first guard failing for a transfer of `amount` from `sender_id` to
+    /// `receiver_id`, of which the receiver is credited `credited`. An unregistered receiver
+    /// fails only under the `Reject` policy.
+    pub(crate) fn first_failed_transfer_guard(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        credited: Balance,
+        receiver_policy: UnregisteredReceiverPolicy,
+    ) -> TransferCheck {
+        if sender_id == receiver_id {
+            return TransferCheck::SelfTransfer;
+        }
+        if amount == 0 {
+            return TransferCheck::ZeroAmount;
+        }
+        if self.paused || self.rebase_progress.is_some() {
+            return TransferCheck::Paused;
+        }
+        if self.frozen_accounts.contains(sender_id) {
+            return TransferCheck::SenderFrozen;
+        }
+        if self.self_locks.contains_key(sender_id) {
+            return TransferCheck::SenderSelfLocked;
+        }
+        if !self.is_receiver_allowed(sender_id, receiver_id) {
+            return TransferCheck::ReceiverNotAllowed;
+        }
+        if let Some(precision) = self.transfer_precision {
+            let unit = 10u128.pow(u32::from(self.ft_metadata().decimals - precision));
+            if amount % unit != 0 {
+                return TransferCheck::InvalidPrecision;
+            }
+        }
+        if receiver_policy == UnregisteredReceiverPolicy::Reject
+            && !self.token.accounts.contains_key(receiver_id)
+        {
+            return TransferCheck::ReceiverUnregistered;
+        }
+        let balance = self.token.accounts.get(sender_id).unwrap_or(0);
+        if balance < amount {
+            return TransferCheck::InsufficientBalance;
+        }
+        let locked = self.locked_balance_of(sender_id.clone()).0;
+        if balance - amount < locked {
+            return TransferCheck::BalanceLocked;
+        }
+        if self
+            .min_remaining_balance
+            .is_some_and(|min_remaining| balance - amount < min_remaining)
+        {
+            return TransferCheck::BelowMinRemainingBalance;
+        }
+        if self.exceeds_volume_cap(amount) {
+            return TransferCheck::VolumeCapReached;
+        }
+        if self.exceeds_max_holding(receiver_id, credited) {
+            return TransferCheck::ExceedsMaxHolding;
+        }
+        TransferCheck::Ok
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::rebase::RebaseProgress;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Funds user1 with 1_000 tokens, user2 is registered.
+    fn setup_funded() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        (contract, context)
+    }
+
+    fn check(contract: &Contract, receiver_id: AccountId, amount: u128) -> TransferCheck {
+        contract.check_transfer(user1(), receiver_id, amount.into())
+    }
+
+    #[test]
+    fn test_check_transfer_ok() {
+        let (contract, _) = setup_funded();
+
+        assert_eq!(check(&contract, user2(), 1_000), TransferCheck::Ok);
+    }
+
+    #[test]
+    fn test_check_transfer_amounts() {
+        let (contract, _) = setup_funded();
+
+        assert_eq!(check(&contract, user1(), 1), TransferCheck::SelfTransfer);
+        assert_eq!(check(&contract, user2(), 0), TransferCheck::ZeroAmount);
+        assert_eq!(
+            check(&contract, user2(), 1_001),
+            TransferCheck::InsufficientBalance
+        );
+        assert_eq!(
+            check(&contract, "nobody.near".parse().unwrap(), 1),
+            TransferCheck::ReceiverUnregistered
+        );
+    }
+
+    #[test]
+    fn test_check_transfer_admin_guards() {
+        let (mut contract, _) = setup_funded();
+
+        contract.lock_balance(user1(), 600.into(), u64::MAX.into());
+        assert_eq!(check(&contract, user2(), 401), TransferCheck::BalanceLocked);
+
+        contract.set_transfer_precision(Some(22));
+        assert_eq!(
+            check(&contract, user2(), 150),
+            TransferCheck::InvalidPrecision
+        );
+
+        contract.freeze_account(user1());
+        assert_eq!(check(&contract, user2(), 100), TransferCheck::SenderFrozen);
+
+        contract.set_paused(true, None);
+        assert_eq!(check(&contract, user2(), 100), TransferCheck::Paused);
+        // checking doesn't panic nor move tokens
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+    }
+
+    #[test]
+    fn test_check_transfer_sender_guards() {
+        let (mut contract, mut context) = setup_funded();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_allowed_receivers(vec![owner()]);
+        assert_eq!(
+            check(&contract, user2(), 100),
+            TransferCheck::ReceiverNotAllowed
+        );
+        assert_eq!(check(&contract, owner(), 100), TransferCheck::Ok);
+
+        contract.set_self_lock(true);
+        assert_eq!(
+            check(&contract, owner(), 100),
+            TransferCheck::SenderSelfLocked
+        );
+    }
+
+    #[test]
+    fn test_check_transfer_limits() {
+        let (mut contract, _) = setup_funded();
+
+        contract.set_max_balance_per_account(Some(500.into()));
+        assert_eq!(
+            check(&contract, user2(), 501),
+            TransferCheck::ExceedsMaxHolding
+        );
+        assert_eq!(check(&contract, user2(), 500), TransferCheck::Ok);
+
+        // the funding transfer already used 1_000 of the epoch volume
+        contract.set_volume_cap_per_epoch(Some(1_400.into()));
+        assert_eq!(
+            check(&contract, user2(), 401),
+            TransferCheck::VolumeCapReached
+        );
+        assert_eq!(check(&contract, user2(), 400), TransferCheck::Ok);
+
+        contract.set_min_remaining_balance(Some(700.into()));
+        assert_eq!(
+            check(&contract, user2(), 301),
+            TransferCheck::BelowMinRemainingBalance
+        );
+        assert_eq!(check(&contract, user2(), 300), TransferCheck::Ok);
+    }
+
+    #[test]
+    fn test_check_transfer_during_rebase() {
+        let (mut contract, _) = setup_funded();
+
+        contract.rebase_progress = Some(RebaseProgress {
+            numerator: 2.into(),
+            denominator: 1.into(),
+            next_index: 1,
+        });
+        assert_eq!(check(&contract, user2(), 100), TransferCheck::Paused);
+    }
+
+    #[test]
+    fn test_expected_refund_if_unregistered() {
+        let (contract, _) = setup_funded();
+
+        assert_eq!(
+            contract
+                .expected_refund_if_unregistered("nobody.near".parse().unwrap(), 500.into())
+                .0,
+            500
+        );
+        // the refund of a registered receiver is only known once ft_on_transfer returns
+        assert_eq!(
+            contract
+                .expected_refund_if_unregistered(user2(), 500.into())
+                .0,
+            0
+        );
+    }
+
+    #[test]
+    fn test_preview_transfer_effects_near_volume_cap() {
+        let (mut contract, mut context) = setup_funded();
+        contract.set_transfer_fee(100);
+        contract.set_volume_cap_per_epoch(Some(1_500.into()));
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user2(), 200.into(), None);
+
+        let effects = contract.preview_transfer_effects(user1(), user2(), 300.into());
+        assert_eq!(
+            effects,
+            TransferEffects {
+                fee: 3.into(),
+                burn: 0.into(),
+                credited: 297.into(),
+                remaining_epoch_volume: Some(300.into()),
+                crosses_volume_cap: false,
+            }
+        );
+        assert!(
+            contract
+                .preview_transfer_effects(user1(), user2(), 301.into())
+                .crosses_volume_cap
+        );
+        // previewing doesn't move tokens
+        assert_eq!(contract.ft_balance_of(user1()).0, 800);
+    }
+
+    #[test]
+    fn test_can_mint_zero_amount() {
+        let (contract, _) = setup_funded();
+
+        assert_eq!(contract.can_mint(0.into()), MintCheck::ZeroAmount);
+        assert_eq!(contract.can_mint(1.into()), MintCheck::Ok);
+    }
+
+    #[test]
+    fn test_can_mint() {
+        let (mut contract, mut context) = setup_funded();
+        assert_eq!(contract.can_mint(1.into()), MintCheck::Ok);
+
+        contract.set_max_supply(Some(contract.ft_total_supply()));
+        assert_eq!(contract.can_mint(1.into()), MintCheck::ExceedsMaxSupply);
+        assert_eq!(contract.can_mint(0.into()), MintCheck::ZeroAmount);
+
+        contract.mint_unlock_ns = Some(1_000);
+        assert_eq!(contract.can_mint(0.into()), MintCheck::MintLocked);
+        testing_env!(context.block_timestamp(1_000).build());
+        assert_eq!(contract.can_mint(0.into()), MintCheck::ZeroAmount);
+
+        contract.set_minting_paused(true);
+        assert_eq!(contract.can_mint(0.into()), MintCheck::MintingPaused);
+
+        contract.set_paused(true, None);
+        assert_eq!(contract.can_mint(0.into()), MintCheck::Paused);
+
+        contract.finalize();
+        assert_eq!(contract.can_mint(0.into()), MintCheck::Finalized);
+    }
+}
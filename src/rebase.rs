@@ -0,0 +1,232 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, env, near, require};
+
+use crate::errors::{
+    ERR_INVALID_REBASE_RATIO, ERR_NO_REBASE_IN_PROGRESS, ERR_REBASE_IN_PROGRESS,
+    ERR_REBASE_OVERFLOW,
+};
+use crate::events::emit_event;
+use crate::math::{checked_add_supply, checked_sub_balance};
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{Contract, ContractExt};
+
+/// State of a rebase spanning several calls, returned by `get_rebase_progress`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebaseProgress {
+    pub numerator: U128,
+    pub denominator: U128,
+    /// Index in the holder set of the next account to scale.
+    pub next_index: u64,
+}
+
+/// Data of the `rebase` event emitted once every holder was scaled.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct Rebase {
+    numerator: U128,
+    denominator: U128,
+    total_supply: U128,
+}
+
+#[near]
+impl Contract {
+    /// Scales the balance of every holder, and the total supply with them, by
+    /// `numerator / denominator`, rounding each balance down. At most `MAX_ITERATED_ACCOUNTS`
+    /// holders are scaled per call: with more holders, call `continue_rebase` until
+    /// `get_rebase_progress` returns `None`. Transfers and minting are blocked until then.
+    /// Accounts registered before the holder set was introduced aren't scaled, nor is the
+    /// contract account, whose tokens back vesting grants and held transfers that are owed
+    /// in unscaled amounts.
+    #[payable]
+    pub fn rebase(&mut self, numerator: U128, denominator: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_no_rebase_in_progress();
+        require!(
+            numerator.0 > 0 && denominator.0 > 0,
+            ERR_INVALID_REBASE_RATIO
+        );
+        self.internal_rebase_page(RebaseProgress {
+            numerator,
+            denominator,
+            next_index: 0,
+        });
+    }
+
+    /// Scales the next page of holders of the rebase in progress.
+    #[payable]
+    pub fn continue_rebase(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        let progress = self
+            .rebase_progress
+            .take()
+            .unwrap_or_else(|| env::panic_str(ERR_NO_REBASE_IN_PROGRESS));
+        self.internal_rebase_page(progress);
+    }
+
+    pub fn get_rebase_progress(&self) -> Option<RebaseProgress> {
+        self.rebase_progress.clone()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_no_rebase_in_progress(&self) {
+        require!(self.rebase_progress.is_none(), ERR_REBASE_IN_PROGRESS);
+    }
+
+    fn internal_rebase_page(&mut self, mut progress: RebaseProgress) {
+        let holder_count = self.holders.len();
+        let end = holder_count.min(progress.next_index + MAX_ITERATED_ACCOUNTS);
+        let escrow_id = env::current_account_id();
+        for index in progress.next_index..end {
+            let account_id = self.holders.as_vector().get(index).unwrap();
+            if account_id == escrow_id {
+                continue;
+            }
+            let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+            let scaled = balance
+                .checked_mul(progress.numerator.0)
+                .unwrap_or_else(|| env::panic_str(ERR_REBASE_OVERFLOW))
+                / progress.denominator.0;
+            self.token.accounts.insert(&account_id, &scaled);
+            self.track_holder_balance(&account_id, balance);
+            self.token.total_supply = checked_add_supply(
+                checked_sub_balance(self.token.total_supply, balance),
+                scaled,
+            );
+        }
+        progress.next_index = end;
+
+        if end < holder_count {
+            self.rebase_progress = Some(progress);
+        } else {
+            self.record_supply_checkpoint();
+            emit_event(
+                "rebase",
+                Rebase {
+                    numerator: progress.numerator,
+                    denominator: progress.denominator,
+                    total_supply: self.token.total_supply.into(),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, AccountId, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    fn as_owner(context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+    }
+
+    #[test]
+    fn test_rebase_doubles_balances() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        as_owner(&mut context);
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.ft_transfer(user2(), 3.into(), None);
+
+        contract.rebase(2.into(), 1.into());
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 2_000);
+        assert_eq!(contract.ft_balance_of(user2()).0, 6);
+        assert_eq!(
+            contract.ft_balance_of(owner()).0,
+            2 * (TOTAL_SUPPLY - 1_003)
+        );
+        assert_eq!(contract.ft_total_supply().0, 2 * TOTAL_SUPPLY);
+        assert_eq!(contract.get_rebase_progress(), None);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""event":"rebase""#)));
+    }
+
+    #[test]
+    fn test_rebase_across_calls() {
+        let (mut contract, mut context) = setup();
+        let holders: Vec<AccountId> = (0..MAX_ITERATED_ACCOUNTS)
+            .map(|i| format!("holder-{}.near", i).parse().unwrap())
+            .collect();
+        for account_id in &holders {
+            contract.internal_register_account(account_id);
+            contract.token.internal_deposit(account_id, 10);
+        }
+        as_owner(&mut context);
+
+        contract.rebase(1.into(), 2.into());
+        assert_eq!(
+            contract.get_rebase_progress(),
+            Some(RebaseProgress {
+                numerator: 1.into(),
+                denominator: 2.into(),
+                next_index: MAX_ITERATED_ACCOUNTS,
+            })
+        );
+
+        contract.continue_rebase();
+        assert_eq!(contract.get_rebase_progress(), None);
+        assert!(holders
+            .iter()
+            .all(|account_id| contract.ft_balance_of(account_id.clone()).0 == 5));
+        assert_eq!(
+            contract.ft_total_supply().0,
+            TOTAL_SUPPLY / 2 + 5 * u128::from(MAX_ITERATED_ACCOUNTS)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "A rebase is in progress")]
+    fn test_transfer_blocked_during_rebase() {
+        let (mut contract, mut context) = setup();
+        for i in 0..MAX_ITERATED_ACCOUNTS {
+            let account_id: AccountId = format!("holder-{}.near", i).parse().unwrap();
+            contract.internal_register_account(&account_id);
+        }
+        as_owner(&mut context);
+        contract.rebase(2.into(), 1.into());
+
+        contract.ft_transfer("holder-0.near".parse().unwrap(), 1.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rebase ratio must be positive")]
+    fn test_rebase_zero_denominator() {
+        let (mut contract, mut context) = setup();
+        as_owner(&mut context);
+
+        contract.rebase(1.into(), 0.into());
+    }
+
+    #[test]
+    fn test_rebase_keeps_vesting_claimable() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        as_owner(&mut context);
+        contract.create_vesting(user1(), 1_000.into(), 0.into(), 0.into(), 1.into());
+
+        contract.rebase(1.into(), 2.into());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(1)
+            .build());
+        assert_eq!(contract.claim_vested().0, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+    }
+}
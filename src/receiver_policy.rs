@@ -0,0 +1,487 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde_json;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId, PromiseResult};
+
+use crate::errors::{
+    ERR_NOTHING_TO_CLAIM, ERR_NO_CLAIM_WINDOW, ERR_QUERY_TOO_LARGE, ERR_ZERO_AMOUNT,
+};
+use crate::math::{checked_add_balance, checked_sub_balance};
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{emit_transfers, Contract, ContractExt};
+
+/// What `ft_transfer` does when the receiver isn't registered.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum UnregisteredReceiverPolicy {
+    /// The transfer panics.
+    #[default]
+    Reject,
+    /// The receiver is registered, its storage paid from the sender's attached deposit like
+    /// in `ft_transfer_and_register`.
+    AutoRegister,
+    /// The tokens are held by the contract account until the receiver registers and calls
+    /// `claim_pending`.
+    HoldForClaim,
+}
+
+/// Tokens held for an unregistered receiver, kept per sender so they can be sent back once
+/// the claim window ends.
+#[near(serializers = [borsh])]
+pub struct HeldTransfer {
+    pub sender_id: AccountId,
+    pub amount: Balance,
+    pub held_at_ns: u64,
+}
+
+#[near]
+impl Contract {
+    #[payable]
+    pub fn set_unregistered_receiver_policy(&mut self, policy: UnregisteredReceiverPolicy) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Unregistered receiver policy set to {:?}", policy);
+        self.unregistered_receiver_policy = policy;
+    }
+
+    pub fn get_unregistered_receiver_policy(&self) -> UnregisteredReceiverPolicy {
+        self.unregistered_receiver_policy
+    }
+
+    /// Sets how long held tokens can be claimed before `sweep_expired_claims` can send them
+    /// back, or lets them wait forever with `None`. Applies to tokens already held too.
+    #[payable]
+    pub fn set_claim_window(&mut self, window_ns: Option<U64>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.claim_window_ns = window_ns.map(|window_ns| window_ns.0);
+    }
+
+    pub fn get_claim_window(&self) -> Option<U64> {
+        self.claim_window_ns.map(U64)
+    }
+
+    /// Returns the tokens held for `account_id` that it can still claim.
+    pub fn get_pending_claim(&self, account_id: AccountId) -> U128 {
+        self.pending_claims
+            .get(&account_id)
+            .unwrap_or_default()
+            .iter()
+            .filter(|held| !self.is_claim_expired(held))
+            .map(|held| held.amount)
+            .sum::<Balance>()
+            .into()
+    }
+
+    /// Moves the tokens held for the caller while it wasn't registered to its balance.
+    /// Tokens whose claim window ended are left for `sweep_expired_claims`. Returns the
+    /// claimed amount.
+    #[payable]
+    pub fn claim_pending(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let (expired, claimable): (Vec<HeldTransfer>, Vec<HeldTransfer>) = self
+            .pending_claims
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|held| self.is_claim_expired(held));
+        let amount: Balance = claimable.iter().map(|held| held.amount).sum();
+        require!(amount > 0, ERR_NOTHING_TO_CLAIM);
+        self.internal_set_held_transfers(&account_id, expired);
+
+        self.internal_release_escrow(&account_id, amount, "pending claim");
+        self.pending_claims_escrowed = checked_sub_balance(self.pending_claims_escrowed, amount);
+        amount.into()
+    }
+
+    /// Sends the tokens held for `account_ids` whose claim window ended back to their
+    /// senders, or to the treasury when a sender can't receive them anymore. Returns the
+    /// swept amount.
+    #[payable]
+    pub fn sweep_expired_claims(&mut self, account_ids: Vec<AccountId>) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.claim_window_ns.is_some(), ERR_NO_CLAIM_WINDOW);
+        require!(
+            account_ids.len() as u64 <= MAX_ITERATED_ACCOUNTS,
+            ERR_QUERY_TOO_LARGE
+        );
+        let mut swept: Balance = 0;
+        for account_id in account_ids {
+            let (expired, claimable): (Vec<HeldTransfer>, Vec<HeldTransfer>) = self
+                .pending_claims
+                .get(&account_id)
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|held| self.is_claim_expired(held));
+            if expired.is_empty() {
+                continue;
+            }
+            self.internal_set_held_transfers(&account_id, claimable);
+            for held in expired {
+                let refund_id = if self.token.accounts.contains_key(&held.sender_id)
+                    && !self.exceeds_max_holding(&held.sender_id, held.amount)
+                {
+                    held.sender_id
+                } else {
+                    self.treasury_id.clone()
+                };
+                self.internal_release_escrow(&refund_id, held.amount, "expired claim");
+                swept = checked_add_balance(swept, held.amount);
+            }
+        }
+        self.pending_claims_escrowed = checked_sub_balance(self.pending_claims_escrowed, swept);
+        swept.into()
+    }
+}
+
+impl Contract {
+    /// Moves `amount` from `sender_id` to the contract account and holds the credited part
+    /// for the unregistered `receiver_id`. Fees and burns apply as for a regular transfer.
+    pub(crate) fn internal_hold_for_claim(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        require!(amount > 0, ERR_ZERO_AMOUNT);
+        self.assert_not_paused();
+        self.assert_not_frozen(sender_id);
+        self.assert_not_self_locked(sender_id);
+        self.assert_receiver_allowed(sender_id, receiver_id);
+        self.assert_min_remaining(sender_id, amount);
+        self.record_memo(sender_id, memo.as_deref());
+
+        let escrow_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&escrow_id) {
+            self.internal_register_account(&escrow_id);
+        }
+        let legs = self.internal_move(sender_id, &escrow_id, amount, memo);
+        let held = legs[0].amount;
+        self.internal_add_held_transfer(receiver_id, sender_id, held);
+        emit_transfers(sender_id, &legs);
+        log!("Holding {} for {} until claimed", held, receiver_id);
+    }
+
+    /// Resolves a transfer call of `amount` whose sender unregistered before the resolution
+    /// without burning the refund, which the SDK would do: the unused part is moved from the
+    /// receiver to the contract account and held for the sender. Returns the used amount.
+    pub(crate) fn internal_hold_refund(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Balance {
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<U128>(&value)
+                .map_or(amount, |unused_amount| amount.min(unused_amount.0)),
+            PromiseResult::Failed => amount,
+        };
+        let refund = unused_amount.min(self.token.accounts.get(receiver_id).unwrap_or(0));
+        if refund == 0 {
+            return amount;
+        }
+
+        let escrow_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&escrow_id) {
+            self.internal_register_account(&escrow_id);
+        }
+        if receiver_id != &escrow_id {
+            self.internal_token_transfer(
+                receiver_id,
+                &escrow_id,
+                refund,
+                Some("refund held for the sender".to_string()),
+            );
+        }
+        self.internal_add_held_transfer(sender_id, sender_id, refund);
+        log!(
+            "Holding the refund of {} for {} until claimed",
+            refund,
+            sender_id
+        );
+        amount - refund
+    }
+
+    /// Holds `amount` sent by `sender_id` for `receiver_id`. Tokens held before from the
+    /// same sender are merged, restarting their claim window.
+    fn internal_add_held_transfer(
+        &mut self,
+        receiver_id: &AccountId,
+        sender_id: &AccountId,
+        amount: Balance,
+    ) {
+        let now = env::block_timestamp();
+        let mut held_transfers = self.pending_claims.get(receiver_id).unwrap_or_default();
+        match held_transfers
+            .iter_mut()
+            .find(|held| &held.sender_id == sender_id)
+        {
+            Some(held) => {
+                held.amount = checked_add_balance(held.amount, amount);
+                held.held_at_ns = now;
+            }
+            None => held_transfers.push(HeldTransfer {
+                sender_id: sender_id.clone(),
+                amount,
+                held_at_ns: now,
+            }),
+        }
+        self.pending_claims.insert(receiver_id, &held_transfers);
+        self.pending_claims_escrowed = checked_add_balance(self.pending_claims_escrowed, amount);
+    }
+
+    fn internal_set_held_transfers(
+        &mut self,
+        receiver_id: &AccountId,
+        held_transfers: Vec<HeldTransfer>,
+    ) {
+        if held_transfers.is_empty() {
+            self.pending_claims.remove(receiver_id);
+        } else {
+            self.pending_claims.insert(receiver_id, &held_transfers);
+        }
+    }
+
+    fn is_claim_expired(&self, held: &HeldTransfer) -> bool {
+        self.claim_window_ns.is_some_and(|window_ns| {
+            env::block_timestamp() >= held.held_at_ns.saturating_add(window_ns)
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{current, owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    fn set_policy(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        policy: UnregisteredReceiverPolicy,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_unregistered_receiver_policy(policy);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_reject_policy_by_default() {
+        let (mut contract, mut context) = setup();
+        assert_eq!(
+            contract.get_unregistered_receiver_policy(),
+            UnregisteredReceiverPolicy::Reject
+        );
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 100.into(), None);
+    }
+
+    #[test]
+    fn test_auto_register_policy() {
+        let (mut contract, mut context) = setup();
+        set_policy(
+            &mut contract,
+            &mut context,
+            UnregisteredReceiverPolicy::AutoRegister,
+        );
+
+        let min_balance = contract.storage_balance_bounds().min;
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(min_balance.saturating_add(NearToken::from_yoctonear(1)))
+            .build());
+        contract.ft_transfer(user1(), 100.into(), None);
+
+        assert!(contract.storage_balance_of(user1()).is_some());
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_auto_register_policy_requires_storage_deposit() {
+        let (mut contract, mut context) = setup();
+        set_policy(
+            &mut contract,
+            &mut context,
+            UnregisteredReceiverPolicy::AutoRegister,
+        );
+
+        contract.ft_transfer(user1(), 100.into(), None);
+    }
+
+    #[test]
+    fn test_hold_for_claim_policy() {
+        let (mut contract, mut context) = setup();
+        set_policy(
+            &mut contract,
+            &mut context,
+            UnregisteredReceiverPolicy::HoldForClaim,
+        );
+
+        contract.ft_transfer(user1(), 100.into(), None);
+        contract.ft_transfer(user1(), 50.into(), None);
+
+        assert!(contract.storage_balance_of(user1()).is_none());
+        assert_eq!(contract.get_pending_claim(user1()).0, 150);
+        assert_eq!(contract.ft_balance_of(current()).0, 150);
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY - 150);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert_eq!(contract.claim_pending().0, 150);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 150);
+        assert_eq!(contract.ft_balance_of(current()).0, 0);
+        assert_eq!(contract.get_pending_claim(user1()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn test_claim_pending_without_held_tokens() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.claim_pending();
+    }
+
+    const CLAIM_WINDOW: u64 = 1_000;
+
+    /// Holds 100 tokens sent by `user2` at timestamp 0 for the unregistered `user1`, with a
+    /// claim window of `CLAIM_WINDOW`.
+    fn setup_held_transfer() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user2());
+        set_policy(
+            &mut contract,
+            &mut context,
+            UnregisteredReceiverPolicy::HoldForClaim,
+        );
+        contract.set_claim_window(Some(CLAIM_WINDOW.into()));
+        contract.ft_transfer(user2(), 1_000.into(), None);
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(0)
+            .build());
+        contract.ft_transfer(user1(), 100.into(), None);
+        assert_eq!(contract.get_pending_claim(user1()).0, 100);
+
+        (contract, context)
+    }
+
+    fn claim_at(contract: &mut Contract, context: &mut VMContextBuilder, timestamp: u64) -> U128 {
+        register_user(contract, context, user1());
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.claim_pending()
+    }
+
+    fn sweep_at(contract: &mut Contract, context: &mut VMContextBuilder, timestamp: u64) -> U128 {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.sweep_expired_claims(vec![user1()])
+    }
+
+    #[test]
+    fn test_claim_within_window() {
+        let (mut contract, mut context) = setup_held_transfer();
+
+        assert_eq!(
+            claim_at(&mut contract, &mut context, CLAIM_WINDOW - 1).0,
+            100
+        );
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+        assert_eq!(sweep_at(&mut contract, &mut context, CLAIM_WINDOW).0, 0);
+        assert_eq!(contract.ft_balance_of(user2()).0, 900);
+    }
+
+    #[test]
+    fn test_sweep_expired_claims_to_sender() {
+        let (mut contract, mut context) = setup_held_transfer();
+
+        assert_eq!(sweep_at(&mut contract, &mut context, CLAIM_WINDOW - 1).0, 0);
+        assert_eq!(sweep_at(&mut contract, &mut context, CLAIM_WINDOW).0, 100);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(current()).0, 0);
+        assert_eq!(contract.get_pending_claim(user1()).0, 0);
+        assert_eq!(contract.supply_metrics().reserved.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn test_claim_after_window_fails() {
+        let (mut contract, mut context) = setup_held_transfer();
+
+        assert_eq!(contract.get_pending_claim(user1()).0, 100);
+        claim_at(&mut contract, &mut context, CLAIM_WINDOW);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim window is not set")]
+    fn test_sweep_without_claim_window() {
+        let (mut contract, mut context) = setup_held_transfer();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_claim_window(None);
+        sweep_at(&mut contract, &mut context, CLAIM_WINDOW);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract paused")]
+    fn test_claim_while_paused_panics() {
+        let (mut contract, mut context) = setup_held_transfer();
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_paused(true, None);
+
+        claim_at(&mut contract, &mut context, CLAIM_WINDOW - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract paused")]
+    fn test_sweep_while_paused_panics() {
+        let (mut contract, mut context) = setup_held_transfer();
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_paused(true, None);
+
+        sweep_at(&mut contract, &mut context, CLAIM_WINDOW);
+    }
+}
@@ -0,0 +1,125 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{assert_one_yocto, near, AccountId};
+
+use crate::events::emit_event;
+use crate::{Contract, ContractExt};
+
+/// Data of the `transfer_detail` event emitted for each transfer when rich events are on.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct TransferDetail<'a> {
+    sender_id: &'a AccountId,
+    receiver_id: &'a AccountId,
+    amount: U128,
+    fee: U128,
+    burned: U128,
+    credited: U128,
+}
+
+#[near]
+impl Contract {
+    /// Adds a `transfer_detail` event with the fee and burn breakdown to every transfer, on
+    /// top of the standard `ft_transfer` event. Off by default.
+    #[payable]
+    pub fn set_rich_events(&mut self, enabled: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.rich_events = enabled;
+    }
+
+    pub fn is_rich_events(&self) -> bool {
+        self.rich_events
+    }
+}
+
+impl Contract {
+    /// Emits the `transfer_detail` event of a transfer if rich events are on.
+    pub(crate) fn emit_transfer_detail(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        fee: Balance,
+        burned: Balance,
+        credited: Balance,
+    ) {
+        if !self.rich_events {
+            return;
+        }
+        emit_event(
+            "transfer_detail",
+            TransferDetail {
+                sender_id,
+                receiver_id,
+                amount: amount.into(),
+                fee: fee.into(),
+                burned: burned.into(),
+                credited: credited.into(),
+            },
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Makes user1 send 1_000 to user2 and returns the logs.
+    fn transfer_with_fee(contract: &mut Contract, context: &mut VMContextBuilder) -> Vec<String> {
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user2(), 1_000.into(), None);
+        get_logs()
+    }
+
+    /// Funds user1 with 2_000 tokens and sets a 1% transfer fee.
+    fn setup_fee() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 2_000.into(), None);
+        contract.set_transfer_fee(100);
+        (contract, context)
+    }
+
+    #[test]
+    fn test_transfer_detail_emitted_with_rich_events() {
+        let (mut contract, mut context) = setup_fee();
+        contract.set_rich_events(true);
+
+        let logs = transfer_with_fee(&mut contract, &mut context);
+
+        assert!(logs.contains(&format!(
+            r#"EVENT_JSON:{{"standard":"publicai","version":"1.0.0","event":"transfer_detail","data":[{{"sender_id":"{}","receiver_id":"{}","amount":"1000","fee":"10","burned":"0","credited":"990"}}]}}"#,
+            user1(),
+            user2()
+        )));
+        assert!(logs
+            .iter()
+            .any(|log| log.contains(r#""event":"ft_transfer""#)));
+    }
+
+    #[test]
+    fn test_only_standard_events_by_default() {
+        let (mut contract, mut context) = setup_fee();
+        assert!(!contract.is_rich_events());
+
+        let logs = transfer_with_fee(&mut contract, &mut context);
+
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#""standard":"nep141""#));
+    }
+}
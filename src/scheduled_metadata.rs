@@ -0,0 +1,179 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::json_types::U64;
+use near_sdk::{assert_one_yocto, env, log, near, require};
+
+use crate::errors::{ERR_NO_SCHEDULED_METADATA, ERR_SCHEDULED_METADATA_NOT_EFFECTIVE};
+use crate::{Contract, ContractExt};
+
+/// Metadata announced in advance, which anyone can activate once `effective_at_ns` passed.
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct ScheduledMetadata {
+    pub metadata: FungibleTokenMetadata,
+    pub effective_at_ns: U64,
+}
+
+#[near]
+impl Contract {
+    /// Schedules `metadata` to replace the current metadata at `effective_at_ns`, replacing
+    /// any update scheduled before. The decimals can't change.
+    #[payable]
+    pub fn schedule_metadata_update(
+        &mut self,
+        metadata: FungibleTokenMetadata,
+        effective_at_ns: U64,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_metadata_not_locked();
+        metadata.assert_valid();
+        self.assert_same_decimals(&metadata);
+        log!(
+            "Metadata update to {} ({}) scheduled at {}",
+            metadata.name,
+            metadata.symbol,
+            effective_at_ns.0
+        );
+        self.scheduled_metadata = Some(ScheduledMetadata {
+            metadata,
+            effective_at_ns,
+        });
+    }
+
+    /// Drops the scheduled metadata update, if any.
+    #[payable]
+    pub fn cancel_scheduled_metadata(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.scheduled_metadata = None;
+    }
+
+    /// Activates the scheduled metadata once its effective time passed. Anyone can call it.
+    /// The name and symbol still follow the metadata change cooldown.
+    pub fn apply_scheduled_metadata(&mut self) {
+        let scheduled = self
+            .scheduled_metadata
+            .take()
+            .unwrap_or_else(|| env::panic_str(ERR_NO_SCHEDULED_METADATA));
+        require!(
+            env::block_timestamp() >= scheduled.effective_at_ns.0,
+            ERR_SCHEDULED_METADATA_NOT_EFFECTIVE
+        );
+        self.internal_set_metadata(scheduled.metadata);
+    }
+
+    pub fn get_scheduled_metadata(&self) -> Option<ScheduledMetadata> {
+        self.scheduled_metadata.clone()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::metadata::FungibleTokenMetadataProvider;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{metadata, owner, setup, user1};
+
+    const EFFECTIVE_AT: u64 = 1_000;
+
+    fn rebranded() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            name: "Rebranded token".to_string(),
+            symbol: "REBRAND".to_string(),
+            ..metadata()
+        }
+    }
+
+    fn schedule(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        metadata: FungibleTokenMetadata,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.schedule_metadata_update(metadata, EFFECTIVE_AT.into());
+    }
+
+    fn apply_at(contract: &mut Contract, context: &mut VMContextBuilder, timestamp: u64) {
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(0))
+            .block_timestamp(timestamp)
+            .build());
+        contract.apply_scheduled_metadata();
+    }
+
+    #[test]
+    fn test_schedule_metadata_update() {
+        let (mut contract, mut context) = setup();
+
+        schedule(&mut contract, &mut context, rebranded());
+
+        let scheduled = contract.get_scheduled_metadata().unwrap();
+        assert_eq!(scheduled.metadata.symbol, "REBRAND");
+        assert_eq!(scheduled.effective_at_ns.0, EFFECTIVE_AT);
+        assert_eq!(contract.ft_metadata().symbol, metadata().symbol);
+    }
+
+    #[test]
+    fn test_apply_scheduled_metadata_after_effective_time() {
+        let (mut contract, mut context) = setup();
+
+        schedule(&mut contract, &mut context, rebranded());
+        apply_at(&mut contract, &mut context, EFFECTIVE_AT);
+
+        assert_eq!(contract.ft_metadata().name, "Rebranded token");
+        assert_eq!(contract.ft_metadata().symbol, "REBRAND");
+        assert!(contract.get_scheduled_metadata().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Scheduled metadata is not effective yet")]
+    fn test_apply_scheduled_metadata_too_early() {
+        let (mut contract, mut context) = setup();
+
+        schedule(&mut contract, &mut context, rebranded());
+        apply_at(&mut contract, &mut context, EFFECTIVE_AT - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No metadata update is scheduled")]
+    fn test_apply_without_scheduled_metadata() {
+        let (mut contract, mut context) = setup();
+
+        schedule(&mut contract, &mut context, rebranded());
+        contract.cancel_scheduled_metadata();
+        apply_at(&mut contract, &mut context, EFFECTIVE_AT);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't change decimals")]
+    fn test_schedule_metadata_update_with_other_decimals() {
+        let (mut contract, mut context) = setup();
+
+        schedule(
+            &mut contract,
+            &mut context,
+            FungibleTokenMetadata {
+                decimals: 18,
+                ..metadata()
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_schedule_metadata_update_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.schedule_metadata_update(rebranded(), EFFECTIVE_AT.into());
+    }
+}
@@ -0,0 +1,912 @@
+use near_contract_standards::fungible_token::Balance;
+use near_contract_standards::storage_management::{StorageBalance, StorageManagement};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{
+    assert_one_yocto, env, log, near, require, AccountId, NearToken, Promise, StorageUsage,
+};
+
+use crate::errors::{
+    ERR_INSUFFICIENT_STORAGE_DEPOSIT, ERR_PROTECTED_ACCOUNT, ERR_QUERY_TOO_LARGE,
+    ERR_REQUIRES_DEPOSIT, ERR_STORAGE_BELOW_BASE, ERR_STORAGE_OVERPAYMENT,
+    ERR_STORAGE_TOPUP_REQUIRED, ERR_UNCLAIMED_VESTING, ERR_UNREGISTER_POSITIVE_BALANCE,
+};
+use crate::math::checked_sub_balance;
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{Contract, ContractExt};
+
+/// With overpayment rejection on, a storage deposit above this multiple of the minimum
+/// storage balance panics instead of being refunded.
+pub const GROSS_OVERPAYMENT_FACTOR: u128 = 2;
+
+/// Split of the NEAR held by the contract, returned by `storage_accounting`.
+#[near(serializers = [json])]
+pub struct StorageAccounting {
+    /// Total NEAR balance of the contract account.
+    pub contract_balance: NearToken,
+    /// NEAR locked to pay for the storage the contract currently uses, registered accounts
+    /// included.
+    pub locked_for_storage: NearToken,
+    /// NEAR that can be withdrawn without breaking the storage staking requirement.
+    pub free: NearToken,
+}
+
+/// Whether the contract holds enough NEAR to operate, returned by `funding_status`.
+#[near(serializers = [json])]
+pub struct FundingStatus {
+    /// Total NEAR balance of the contract account.
+    pub contract_near_balance: NearToken,
+    /// NEAR the contract must keep for the storage it uses, registered accounts included.
+    pub storage_obligations: NearToken,
+    /// NEAR left for refunds and gas once storage is covered, zero if it isn't.
+    pub surplus: NearToken,
+}
+
+/// Storage footprint of the contract, returned by `storage_stats`.
+#[near(serializers = [json])]
+pub struct StorageStats {
+    /// Bytes currently used by the contract account, code included.
+    pub total_bytes: U64,
+    /// Number of registered accounts.
+    pub num_accounts: u64,
+    /// `total_bytes` divided by `num_accounts`, rounded down.
+    pub bytes_per_account_avg: U64,
+}
+
+/// Outcome of `storage_unregister_detailed`.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub struct UnregisterResult {
+    pub unregistered: bool,
+    /// NEAR sent back to the account for its released storage.
+    pub refunded: NearToken,
+    /// Tokens burned because the account was force closed with a positive balance.
+    pub burned: U128,
+}
+
+#[near]
+impl Contract {
+    /// Reports how much of the contract's NEAR is bound to storage and how much is free.
+    pub fn storage_accounting(&self) -> StorageAccounting {
+        let contract_balance = env::account_balance();
+        let locked_for_storage =
+            env::storage_byte_cost().saturating_mul(env::storage_usage().into());
+        StorageAccounting {
+            contract_balance,
+            locked_for_storage,
+            free: contract_balance.saturating_sub(locked_for_storage),
+        }
+    }
+
+    /// Reports whether the contract has NEAR to spare beyond its storage obligations, e.g.
+    /// for storage refunds, for operational checks.
+    pub fn funding_status(&self) -> FundingStatus {
+        let accounting = self.storage_accounting();
+        FundingStatus {
+            contract_near_balance: accounting.contract_balance,
+            storage_obligations: accounting.locked_for_storage,
+            surplus: accounting.free,
+        }
+    }
+
+    /// Sets the storage charged per registered account, in bytes, so that
+    /// `storage_balance_bounds` covers the per-account state of the enabled features
+    /// (allowances, locks...) on top of the balance entry. It can't go below the footprint of
+    /// the balance entry alone. Accounts registered before a change paid the previous bound
+    /// and get that back when they unregister.
+    #[payable]
+    pub fn set_account_storage_usage(&mut self, bytes: StorageUsage) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            bytes >= self.base_account_storage_usage,
+            ERR_STORAGE_BELOW_BASE
+        );
+        log!("Account storage usage set to {} bytes", bytes);
+        self.token.account_storage_usage = bytes;
+    }
+
+    pub fn get_account_storage_usage(&self) -> StorageUsage {
+        self.token.account_storage_usage
+    }
+
+    /// Makes storage deposits above `GROSS_OVERPAYMENT_FACTOR` times the minimum storage
+    /// balance panic, to surface client bugs, instead of refunding the excess. Smaller
+    /// overpayments are still refunded.
+    #[payable]
+    pub fn set_reject_storage_overpayment(&mut self, reject: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.reject_storage_overpayment = reject;
+    }
+
+    pub fn is_reject_storage_overpayment(&self) -> bool {
+        self.reject_storage_overpayment
+    }
+
+    /// Returns the NEAR `account_id` has to attach to `require_storage_topup` to cover the
+    /// current per-account storage, zero if it is already covered or not registered.
+    pub fn storage_topup_required(&self, account_id: AccountId) -> NearToken {
+        self.internal_storage_topup(&account_id)
+    }
+
+    /// Raises the storage deposit of the caller to the current `storage_balance_bounds`
+    /// after the owner raised them, which the features storing per-account state (allowances,
+    /// self lock) require. The excess of the attached deposit is refunded.
+    #[payable]
+    pub fn require_storage_topup(&mut self) -> NearToken {
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.token.accounts.contains_key(&account_id),
+            format!("The account {} is not registered", account_id)
+        );
+        let topup = self.internal_storage_topup(&account_id);
+        let amount = env::attached_deposit();
+        require!(amount >= topup, ERR_INSUFFICIENT_STORAGE_DEPOSIT);
+        self.storage_covered
+            .insert(&account_id, &self.token.account_storage_usage);
+        let refund = amount.saturating_sub(topup);
+        if !refund.is_zero() {
+            Promise::new(account_id).transfer(refund);
+        }
+        topup
+    }
+
+    /// Reports the storage used by the contract and its average per registered account, to
+    /// forecast storage costs as the holder base grows.
+    pub fn storage_stats(&self) -> StorageStats {
+        let total_bytes = env::storage_usage();
+        let num_accounts = self.holders.len();
+        StorageStats {
+            total_bytes: total_bytes.into(),
+            num_accounts,
+            bytes_per_account_avg: total_bytes.checked_div(num_accounts).unwrap_or(0).into(),
+        }
+    }
+
+    /// Returns whether each of `account_ids` is registered, in the same order. At most
+    /// `MAX_ITERATED_ACCOUNTS` accounts can be checked per call.
+    pub fn are_registered(&self, account_ids: Vec<AccountId>) -> Vec<bool> {
+        require!(
+            account_ids.len() as u64 <= MAX_ITERATED_ACCOUNTS,
+            ERR_QUERY_TOO_LARGE
+        );
+        account_ids
+            .iter()
+            .map(|account_id| self.token.accounts.contains_key(account_id))
+            .collect()
+    }
+
+    /// Registers `account_id` if it isn't registered yet and returns `true`.
+    /// Returns `false` and refunds the attached deposit if the account is already registered.
+    /// Panics only if the attached deposit doesn't cover the minimum storage balance.
+    #[payable]
+    pub fn try_storage_register(&mut self, account_id: AccountId) -> bool {
+        self.internal_storage_register(&account_id, env::predecessor_account_id())
+    }
+
+    /// `try_storage_register` for sponsors registering users: whatever the registration
+    /// doesn't use is refunded to `refund_to` instead of the caller.
+    #[payable]
+    pub fn storage_deposit_sponsored(
+        &mut self,
+        account_id: AccountId,
+        refund_to: AccountId,
+    ) -> bool {
+        self.internal_storage_register(&account_id, refund_to)
+    }
+
+    /// Registers `receiver_id` if needed and transfers `amount` to it in a single call.
+    /// One yoctoNEAR of the attached deposit is kept as the transfer confirmation, the storage
+    /// cost of a new receiver is paid from the rest, and whatever remains is refunded.
+    #[payable]
+    pub fn ft_transfer_and_register(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        let one_yocto = NearToken::from_yoctonear(1);
+        require!(env::attached_deposit() >= one_yocto, ERR_REQUIRES_DEPOSIT);
+        let mut refund = env::attached_deposit().saturating_sub(one_yocto);
+
+        if !self.token.accounts.contains_key(&receiver_id) {
+            let min_balance = self.storage_balance_bounds().min;
+            require!(refund >= min_balance, ERR_INSUFFICIENT_STORAGE_DEPOSIT);
+            self.internal_register_account(&receiver_id);
+            refund = refund.saturating_sub(min_balance);
+        }
+
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+        if !refund.is_zero() {
+            Promise::new(sender_id).transfer(refund);
+        }
+    }
+
+    /// `storage_unregister` reporting what happened to the storage deposit and the balance of
+    /// the account instead of a bare `bool`.
+    #[payable]
+    pub fn storage_unregister_detailed(&mut self, force: Option<bool>) -> UnregisterResult {
+        match self.internal_unregister(force) {
+            Some((balance, refunded)) => UnregisterResult {
+                unregistered: true,
+                refunded,
+                burned: balance.into(),
+            },
+            None => UnregisterResult {
+                unregistered: false,
+                refunded: NearToken::from_near(0),
+                burned: U128(0),
+            },
+        }
+    }
+}
+
+impl Contract {
+    /// Unregisters the caller like the standard `storage_unregister`, burning its balance
+    /// when forced, and clears its per-account state. The refund is the storage balance the
+    /// account paid, not the current bound. Returns the burned balance and the refunded
+    /// storage deposit, `None` if the caller isn't registered.
+    pub(crate) fn internal_unregister(
+        &mut self,
+        force: Option<bool>,
+    ) -> Option<(Balance, NearToken)> {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let Some(balance) = self.token.accounts.get(&account_id) else {
+            log!("The account {} is not registered", account_id);
+            return None;
+        };
+        require!(
+            balance == 0 || force.unwrap_or(false),
+            ERR_UNREGISTER_POSITIVE_BALANCE
+        );
+        let refunded = self
+            .internal_storage_balance(&account_id)
+            .total
+            .saturating_add(NearToken::from_yoctonear(1));
+        self.token.accounts.remove(&account_id);
+        self.track_holder_balance(&account_id, balance);
+        self.token.total_supply = checked_sub_balance(self.token.total_supply, balance);
+        self.internal_clear_account_state(&account_id);
+        if balance > 0 {
+            self.record_burned(balance);
+        }
+        log!("Closed @{} with {}", account_id, balance);
+        Promise::new(account_id).transfer(refunded);
+        Some((balance, refunded))
+    }
+
+    /// Removes the state kept for `account_id` once its balance entry is gone, so none of it
+    /// comes back if the account registers again. Panics if the account has unclaimed
+    /// vesting, which couldn't be claimed anymore, or if transfers credit it (treasury,
+    /// reward pool, burn address), as they would all fail.
+    pub(crate) fn internal_clear_account_state(&mut self, account_id: &AccountId) {
+        require!(
+            account_id != &self.treasury_id
+                && self.reward_pool_id.as_ref() != Some(account_id)
+                && self.burn_address.as_ref() != Some(account_id),
+            ERR_PROTECTED_ACCOUNT
+        );
+        if let Some(mut schedules) = self.vesting.remove(account_id) {
+            require!(
+                schedules.iter().all(|s| s.claimed == s.total),
+                ERR_UNCLAIMED_VESTING
+            );
+            schedules.clear();
+        }
+        self.holders.remove(account_id);
+        self.storage_covered.remove(account_id);
+        self.internal_clear_allowances(account_id);
+        self.balance_locks.remove(account_id);
+        self.self_locks.remove(account_id);
+        self.allowed_receivers.remove(account_id);
+        self.recent_memos.remove(account_id);
+        self.last_active.remove(account_id);
+        self.last_active_epoch.remove(account_id);
+        self.frozen_accounts.remove(account_id);
+    }
+
+    /// Registers `account_id` with the token and tracks it in the holder set.
+    pub(crate) fn internal_register_account(&mut self, account_id: &AccountId) {
+        self.token.internal_register_account(account_id);
+        self.holders.insert(account_id);
+        self.storage_covered
+            .insert(account_id, &self.token.account_storage_usage);
+    }
+
+    /// Panics if overpayment rejection is on and `deposit` grossly exceeds the minimum
+    /// storage balance.
+    pub(crate) fn assert_not_storage_overpayment(&self, deposit: NearToken) {
+        if !self.reject_storage_overpayment {
+            return;
+        }
+        let max_deposit = self
+            .storage_balance_bounds()
+            .min
+            .saturating_mul(GROSS_OVERPAYMENT_FACTOR);
+        require!(deposit <= max_deposit, ERR_STORAGE_OVERPAYMENT);
+    }
+
+    fn internal_storage_register(&mut self, account_id: &AccountId, refund_to: AccountId) -> bool {
+        let amount = env::attached_deposit();
+        self.assert_not_storage_overpayment(amount);
+        if self.token.accounts.contains_key(account_id) {
+            log!("The account {} is already registered", account_id);
+            if !amount.is_zero() {
+                Promise::new(refund_to).transfer(amount);
+            }
+            return false;
+        }
+
+        let min_balance = self.storage_balance_bounds().min;
+        if amount < min_balance {
+            env::panic_str(ERR_INSUFFICIENT_STORAGE_DEPOSIT);
+        }
+        self.internal_register_account(account_id);
+        let refund = amount.saturating_sub(min_balance);
+        if refund > NearToken::from_near(0) {
+            Promise::new(refund_to).transfer(refund);
+        }
+        true
+    }
+
+    /// Returns the NEAR the registered `account_id` is missing to cover the current
+    /// per-account storage. Accounts registered before the covered bytes were tracked are
+    /// assumed to have paid the base bound.
+    fn internal_storage_topup(&self, account_id: &AccountId) -> NearToken {
+        if !self.token.accounts.contains_key(account_id) {
+            return NearToken::from_near(0);
+        }
+        env::storage_byte_cost().saturating_mul(
+            self.token
+                .account_storage_usage
+                .saturating_sub(self.covered_storage(account_id))
+                .into(),
+        )
+    }
+
+    /// Returns the storage bytes `account_id` paid for, when it registered or last topped up.
+    /// Accounts registered before the covered bytes were tracked are assumed to have paid the
+    /// base bound.
+    fn covered_storage(&self, account_id: &AccountId) -> StorageUsage {
+        self.storage_covered
+            .get(account_id)
+            .unwrap_or(self.base_account_storage_usage)
+    }
+
+    /// Returns the storage balance of the registered `account_id`: the NEAR its covered
+    /// storage is worth, which is what it gets back when it unregisters, regardless of later
+    /// changes to the storage bounds.
+    pub(crate) fn internal_storage_balance(&self, account_id: &AccountId) -> StorageBalance {
+        StorageBalance {
+            total: env::storage_byte_cost().saturating_mul(self.covered_storage(account_id).into()),
+            available: NearToken::from_near(0),
+        }
+    }
+
+    /// Panics if `account_id` must top up its storage deposit before storing more state.
+    pub(crate) fn assert_storage_funded(&self, account_id: &AccountId) {
+        require!(
+            self.internal_storage_topup(account_id).is_zero(),
+            ERR_STORAGE_TOPUP_REQUIRED
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2};
+
+    /// Returns the total NEAR sent back by `Transfer` actions of the created receipts.
+    fn refunded() -> NearToken {
+        get_created_receipts()
+            .iter()
+            .flat_map(|receipt| receipt.actions.iter())
+            .filter_map(|action| match action {
+                MockAction::Transfer { deposit, .. } => Some(*deposit),
+                _ => None,
+            })
+            .fold(NearToken::from_near(0), NearToken::saturating_add)
+    }
+
+    /// Turns overpayment rejection on and deposits `deposit` for user1.
+    fn deposit_rejecting_overpayment(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        deposit: NearToken,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_reject_storage_overpayment(true);
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(deposit)
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    fn test_modest_storage_overpayment_refunded() {
+        let (mut contract, mut context) = setup();
+        let min_balance = contract.storage_balance_bounds().min;
+
+        deposit_rejecting_overpayment(
+            &mut contract,
+            &mut context,
+            min_balance.saturating_mul(GROSS_OVERPAYMENT_FACTOR),
+        );
+
+        assert!(contract.storage_balance_of(user1()).is_some());
+        assert_eq!(refunded(), min_balance);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The attached deposit is more than twice the minimum storage balance"
+    )]
+    fn test_gross_storage_overpayment_panics() {
+        let (mut contract, mut context) = setup();
+        let min_balance = contract.storage_balance_bounds().min;
+
+        deposit_rejecting_overpayment(
+            &mut contract,
+            &mut context,
+            min_balance
+                .saturating_mul(GROSS_OVERPAYMENT_FACTOR)
+                .saturating_add(NearToken::from_yoctonear(1)),
+        );
+    }
+
+    #[test]
+    fn test_gross_storage_overpayment_refunded_by_default() {
+        let (mut contract, mut context) = setup();
+        let min_balance = contract.storage_balance_bounds().min;
+        assert!(!contract.is_reject_storage_overpayment());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(min_balance.saturating_mul(10))
+            .build());
+        contract.storage_deposit(None, None);
+
+        assert_eq!(refunded(), min_balance.saturating_mul(9));
+    }
+
+    #[test]
+    fn test_storage_accounting() {
+        let (mut contract, mut context) = setup();
+        let before = contract.storage_accounting();
+
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        register_user(&mut contract, &mut context, accounts(4));
+
+        let accounting = contract.storage_accounting();
+        assert_eq!(
+            accounting.locked_for_storage,
+            env::storage_byte_cost().saturating_mul(env::storage_usage().into())
+        );
+        assert!(accounting.locked_for_storage > before.locked_for_storage);
+        assert_eq!(
+            accounting
+                .locked_for_storage
+                .saturating_add(accounting.free),
+            accounting.contract_balance
+        );
+    }
+
+    #[test]
+    fn test_funding_status_after_registrations() {
+        let (mut contract, mut context) = setup();
+        let before = contract.funding_status();
+        let usage_before = env::storage_usage();
+
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        let status = contract.funding_status();
+        let registrations_cost =
+            env::storage_byte_cost().saturating_mul((env::storage_usage() - usage_before).into());
+        assert_eq!(
+            status.storage_obligations,
+            before
+                .storage_obligations
+                .saturating_add(registrations_cost)
+        );
+        assert_eq!(
+            status.surplus,
+            status
+                .contract_near_balance
+                .saturating_sub(status.storage_obligations)
+        );
+        assert!(status.surplus > NearToken::from_yoctonear(0));
+    }
+
+    #[test]
+    fn test_storage_stats() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        let stats = contract.storage_stats();
+        assert_eq!(stats.total_bytes.0, env::storage_usage());
+        assert_eq!(stats.num_accounts, 3);
+        assert_eq!(stats.bytes_per_account_avg.0, env::storage_usage() / 3);
+    }
+
+    fn set_account_storage_usage(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        bytes: StorageUsage,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_account_storage_usage(bytes);
+    }
+
+    #[test]
+    fn test_register_with_raised_storage_usage() {
+        let (mut contract, mut context) = setup();
+        let base = contract.get_account_storage_usage();
+        set_account_storage_usage(&mut contract, &mut context, base + 100);
+
+        let min = contract.storage_balance_bounds().min;
+        assert_eq!(
+            min,
+            env::storage_byte_cost().saturating_mul((base + 100).into())
+        );
+
+        register_user(&mut contract, &mut context, user1());
+        assert_eq!(contract.storage_balance_of(user1()).unwrap().total, min);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_register_below_raised_storage_usage() {
+        let (mut contract, mut context) = setup();
+        let base = contract.get_account_storage_usage();
+        let base_min = contract.storage_balance_bounds().min;
+        set_account_storage_usage(&mut contract, &mut context, base + 100);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(base_min)
+            .build());
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account storage can't be below the base requirement of the token")]
+    fn test_storage_usage_below_base() {
+        let (mut contract, mut context) = setup();
+        let base = contract.get_account_storage_usage();
+
+        set_account_storage_usage(&mut contract, &mut context, base - 1);
+    }
+
+    #[test]
+    fn test_storage_topup_enables_approvals() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        let base = contract.get_account_storage_usage();
+        set_account_storage_usage(&mut contract, &mut context, base + 100);
+        // accounts registering now pay the raised bound
+        register_user(&mut contract, &mut context, user2());
+        assert!(contract.storage_topup_required(user2()).is_zero());
+
+        let topup = contract.storage_topup_required(user1());
+        assert_eq!(topup, env::storage_byte_cost().saturating_mul(100));
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(topup.saturating_add(NearToken::from_yoctonear(5)))
+            .build());
+        assert_eq!(contract.require_storage_topup(), topup);
+        assert_eq!(refunded(), NearToken::from_yoctonear(5));
+        assert!(contract.storage_topup_required(user1()).is_zero());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_approve(user2(), 100.into());
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 100);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The account storage deposit is below the current bound, call require_storage_topup"
+    )]
+    fn test_approve_requires_storage_topup() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        let base = contract.get_account_storage_usage();
+        set_account_storage_usage(&mut contract, &mut context, base + 100);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_approve(user2(), 100.into());
+    }
+
+    #[test]
+    fn test_are_registered() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user2());
+
+        assert_eq!(
+            contract.are_registered(vec![user1(), owner(), user2(), accounts(4)]),
+            vec![false, true, true, false]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many accounts requested")]
+    fn test_are_registered_above_limit() {
+        let (contract, _) = setup();
+
+        contract.are_registered(vec![owner(); MAX_ITERATED_ACCOUNTS as usize + 1]);
+    }
+
+    #[test]
+    fn test_try_storage_register_new_account() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(contract.storage_balance_bounds().min)
+            .build());
+
+        assert!(contract.try_storage_register(user1()));
+        assert!(contract.storage_balance_of(user1()).is_some());
+    }
+
+    #[test]
+    fn test_try_storage_register_registered_account() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(contract.storage_balance_bounds().min)
+            .build());
+
+        assert!(contract.try_storage_register(user1()));
+        // a repeated registration doesn't panic, it refunds and reports `false`
+        assert!(!contract.try_storage_register(user1()));
+        // the owner was registered in `new`
+        assert!(!contract.try_storage_register(owner()));
+    }
+
+    #[test]
+    fn test_storage_deposit_sponsored_refunds_sponsor() {
+        let (mut contract, mut context) = setup();
+        let sponsor = accounts(5);
+        let extra = NearToken::from_millinear(3);
+
+        testing_env!(context
+            .predecessor_account_id(sponsor.clone())
+            .attached_deposit(contract.storage_balance_bounds().min.saturating_add(extra))
+            .build());
+        assert!(contract.storage_deposit_sponsored(user1(), sponsor.clone()));
+
+        assert!(contract.storage_balance_of(user1()).is_some());
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, sponsor);
+        assert_eq!(refunded(), extra);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_try_storage_register_panics_on_insufficient_deposit() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(100))
+            .build());
+
+        contract.try_storage_register(user1());
+    }
+
+    #[test]
+    fn test_transfer_and_register_new_receiver() {
+        let (mut contract, mut context) = setup();
+        let min_balance = contract.storage_balance_bounds().min;
+        let extra = NearToken::from_millinear(1);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(
+                min_balance
+                    .saturating_add(NearToken::from_yoctonear(1))
+                    .saturating_add(extra)
+            )
+            .build());
+        contract.ft_transfer_and_register(user1(), 100.into(), None);
+
+        assert!(contract.storage_balance_of(user1()).is_some());
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+        assert_eq!(refunded(), extra);
+    }
+
+    #[test]
+    fn test_transfer_and_register_existing_receiver() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        let deposit = contract
+            .storage_balance_bounds()
+            .min
+            .saturating_add(NearToken::from_yoctonear(1));
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(deposit)
+            .build());
+        contract.ft_transfer_and_register(user1(), 100.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+        // everything but the confirmation yoctoNEAR is refunded
+        assert_eq!(refunded(), contract.storage_balance_bounds().min);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_transfer_and_register_panics_on_insufficient_deposit() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(contract.storage_balance_bounds().min)
+            .build());
+        contract.ft_transfer_and_register(user1(), 100.into(), None);
+    }
+
+    #[test]
+    fn test_storage_unregister_detailed() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        let result = contract.storage_unregister_detailed(None);
+
+        assert_eq!(
+            result,
+            UnregisterResult {
+                unregistered: true,
+                refunded: refunded(),
+                burned: U128(0),
+            }
+        );
+        assert_eq!(
+            result.refunded,
+            contract
+                .storage_balance_bounds()
+                .min
+                .saturating_add(NearToken::from_yoctonear(1))
+        );
+        assert!(!contract.storage_unregister_detailed(None).unregistered);
+    }
+
+    #[test]
+    fn test_storage_unregister_detailed_forced() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        let total_supply = contract.ft_total_supply().0;
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        let result = contract.storage_unregister_detailed(Some(true));
+
+        assert!(result.unregistered);
+        assert_eq!(result.burned.0, 1_000);
+        assert_eq!(contract.ft_total_supply().0, total_supply - 1_000);
+    }
+
+    #[test]
+    fn test_unregister_clears_account_state() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_approve(user2(), 500.into());
+        contract.set_self_lock(true);
+        contract.set_allowed_receivers(vec![owner()]);
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.freeze_account(user1());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert!(contract.storage_unregister_detailed(None).unregistered);
+        register_user(&mut contract, &mut context, user1());
+
+        assert_eq!(contract.ft_allowance(user1(), user2()).0, 0);
+        assert!(!contract.is_self_locked(user1()));
+        assert_eq!(contract.get_allowed_receivers(user1()), None);
+        assert!(!contract.is_frozen(user1()));
+    }
+
+    #[test]
+    fn test_unregister_refunds_the_paid_storage_after_a_raise() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        let paid = contract.storage_balance_bounds().min;
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_account_storage_usage(contract.get_account_storage_usage() + 100);
+        assert_eq!(contract.storage_balance_of(user1()).unwrap().total, paid);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        let result = contract.storage_unregister_detailed(None);
+
+        let expected = paid.saturating_add(NearToken::from_yoctonear(1));
+        assert_eq!(result.refunded, expected);
+        assert_eq!(refunded(), expected);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The treasury, the reward pool and the burn address can't be unregistered"
+    )]
+    fn test_treasury_cannot_unregister() {
+        let (mut contract, mut context) = setup();
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.storage_unregister_detailed(Some(true));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The treasury, the reward pool and the burn address can't be unregistered"
+    )]
+    fn test_reward_pool_cannot_unregister() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_reward_pool(Some(user2()));
+
+        testing_env!(context
+            .predecessor_account_id(user2())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.storage_unregister_detailed(None);
+    }
+}
@@ -0,0 +1,526 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::collections::UnorderedSet;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, near, require, AccountId};
+
+use crate::errors::{ERR_QUERY_TOO_LARGE, ERR_TOO_MANY_ACCOUNTS};
+use crate::fees::BPS_DENOMINATOR;
+use crate::math::{checked_add_balance, checked_sub_balance};
+use crate::{Contract, ContractExt};
+
+/// Supply figures returned together by `supply_metrics`.
+#[near(serializers = [json])]
+#[derive(Debug, PartialEq)]
+pub struct SupplyMetrics {
+    pub total: U128,
+    /// Total supply minus the balances of the excluded accounts.
+    pub circulating: U128,
+    /// Combined balance of the excluded accounts.
+    pub locked: U128,
+    /// Tokens burned since deployment, by burns, transfer burns, burned fees and forced
+    /// unregistrations.
+    pub burned: U128,
+    /// Tokens minted since deployment, the initial supply included.
+    pub minted: U128,
+    /// Tokens held by the contract account for vesting schedules and pending claims.
+    pub reserved: U128,
+}
+
+/// Maximum number of accounts a view may iterate over in a single call. Views summing over
+/// more accounts than this panic and point to their paginated variant instead.
+pub const MAX_ITERATED_ACCOUNTS: u64 = 100;
+
+#[near]
+impl Contract {
+    /// Excludes `account_id` (treasury, vesting escrow, burn address...) from the circulating
+    /// supply.
+    #[payable]
+    pub fn add_excluded_account(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.excluded_accounts.insert(&account_id)
+    }
+
+    #[payable]
+    pub fn remove_excluded_account(&mut self, account_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.excluded_accounts.remove(&account_id)
+    }
+
+    /// Replaces the whole set of excluded accounts at once. Duplicated entries are stored once.
+    #[payable]
+    pub fn set_excluded_accounts(&mut self, accounts: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.excluded_accounts.clear();
+        for account_id in &accounts {
+            self.excluded_accounts.insert(account_id);
+        }
+    }
+
+    pub fn get_excluded_accounts(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        paginate_accounts(&self.excluded_accounts, from_index, limit)
+    }
+
+    /// Returns the total supply minus the balances of the excluded accounts.
+    /// Panics if there are more than `MAX_ITERATED_ACCOUNTS` excluded accounts; sum them with
+    /// `ft_excluded_balance` page by page instead.
+    pub fn ft_circulating_supply(&self) -> U128 {
+        require!(
+            self.excluded_accounts.len() <= MAX_ITERATED_ACCOUNTS,
+            ERR_TOO_MANY_ACCOUNTS
+        );
+        let excluded = self.excluded_balance(0, MAX_ITERATED_ACCOUNTS);
+        checked_sub_balance(self.token.total_supply, excluded).into()
+    }
+
+    /// Returns the total supply minus the circulating supply, i.e. the combined balance of the
+    /// excluded accounts. Returns 0 when no account is excluded.
+    pub fn ft_locked_supply(&self) -> U128 {
+        checked_sub_balance(self.token.total_supply, self.ft_circulating_supply().0).into()
+    }
+
+    /// Returns every supply figure in one call. Like `ft_circulating_supply`, panics if there
+    /// are more than `MAX_ITERATED_ACCOUNTS` excluded accounts.
+    pub fn supply_metrics(&self) -> SupplyMetrics {
+        let circulating = self.ft_circulating_supply();
+        SupplyMetrics {
+            total: self.token.total_supply.into(),
+            circulating,
+            locked: checked_sub_balance(self.token.total_supply, circulating.0).into(),
+            burned: self.total_burned.into(),
+            minted: self.total_minted.into(),
+            reserved: checked_add_balance(self.vesting_escrowed, self.pending_claims_escrowed)
+                .into(),
+        }
+    }
+
+    /// Returns the number of accounts holding a positive balance, the contract account
+    /// included, unlike the registered holders, which also count emptied accounts.
+    pub fn nonzero_holder_count(&self) -> u64 {
+        self.nonzero_holders
+    }
+
+    /// Returns a page of the registered accounts with their balances, for off-chain
+    /// snapshots of the ledger. Pages follow the insertion order of the holder set, which is
+    /// stable as long as no account unregisters between two calls: an unregistration moves
+    /// the last account into the freed slot.
+    pub fn export_accounts(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(AccountId, U128)> {
+        paginate_accounts(&self.holders, from_index, limit)
+            .into_iter()
+            .map(|account_id| {
+                let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+                (account_id, U128(balance))
+            })
+            .collect()
+    }
+
+    /// Returns the combined balance of `account_ids`, counting unregistered accounts as zero.
+    /// At most `MAX_ITERATED_ACCOUNTS` accounts can be summed per call.
+    pub fn ft_balance_sum(&self, account_ids: Vec<AccountId>) -> U128 {
+        require!(
+            account_ids.len() as u64 <= MAX_ITERATED_ACCOUNTS,
+            ERR_QUERY_TOO_LARGE
+        );
+        account_ids
+            .iter()
+            .map(|account_id| self.token.accounts.get(account_id).unwrap_or(0))
+            .sum::<Balance>()
+            .into()
+    }
+
+    /// Returns the balance of `account_id` in basis points of the total supply, rounded to
+    /// the nearest. Returns 0 when there is no supply.
+    pub fn ft_ownership_bps(&self, account_id: AccountId) -> u16 {
+        let total_supply = self.token.total_supply;
+        if total_supply == 0 {
+            return 0;
+        }
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        // scales huge supplies down so that `balance * BPS_DENOMINATOR` can't overflow; the
+        // precision lost is far below a basis point
+        let scale = total_supply / (u128::MAX / (2 * BPS_DENOMINATOR)) + 1;
+        let (balance, total_supply) = (balance / scale, total_supply / scale);
+        ((balance * BPS_DENOMINATOR + total_supply / 2) / total_supply) as u16
+    }
+
+    /// Returns the summed balance of a page of excluded accounts.
+    pub fn ft_excluded_balance(&self, from_index: Option<u64>, limit: Option<u64>) -> U128 {
+        let limit = limit.unwrap_or(MAX_ITERATED_ACCOUNTS);
+        require!(limit <= MAX_ITERATED_ACCOUNTS, ERR_TOO_MANY_ACCOUNTS);
+        self.excluded_balance(from_index.unwrap_or(0), limit).into()
+    }
+}
+
+/// Returns a page of `accounts`, at most `MAX_ITERATED_ACCOUNTS` long.
+pub(crate) fn paginate_accounts(
+    accounts: &UnorderedSet<AccountId>,
+    from_index: Option<u64>,
+    limit: Option<u64>,
+) -> Vec<AccountId> {
+    let limit = limit.unwrap_or(MAX_ITERATED_ACCOUNTS);
+    require!(limit <= MAX_ITERATED_ACCOUNTS, ERR_QUERY_TOO_LARGE);
+    accounts
+        .as_vector()
+        .iter()
+        .skip(from_index.unwrap_or(0) as usize)
+        .take(limit as usize)
+        .collect()
+}
+
+impl Contract {
+    /// Credits `amount` to `account_id`, counting it as a holder if it was empty.
+    pub(crate) fn internal_credit(&mut self, account_id: &AccountId, amount: Balance) {
+        let before = self.token.accounts.get(account_id).unwrap_or(0);
+        self.token.internal_deposit(account_id, amount);
+        self.track_holder_balance(account_id, before);
+    }
+
+    /// Debits `amount` from `account_id`, no longer counting it as a holder if emptied.
+    pub(crate) fn internal_debit(&mut self, account_id: &AccountId, amount: Balance) {
+        let before = self.token.accounts.get(account_id).unwrap_or(0);
+        self.token.internal_withdraw(account_id, amount);
+        self.track_holder_balance(account_id, before);
+    }
+
+    /// The SDK transfer, with its `FtTransfer` event and without fees or guards, keeping the
+    /// holder count in step.
+    pub(crate) fn internal_token_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        let sender_balance = self.token.accounts.get(sender_id).unwrap_or(0);
+        let receiver_balance = self.token.accounts.get(receiver_id).unwrap_or(0);
+        self.token
+            .internal_transfer(sender_id, receiver_id, amount, memo);
+        self.track_holder_balance(sender_id, sender_balance);
+        self.track_holder_balance(receiver_id, receiver_balance);
+    }
+
+    /// Updates the count of accounts with a positive balance after the balance of
+    /// `account_id` changed from `before`.
+    pub(crate) fn track_holder_balance(&mut self, account_id: &AccountId, before: Balance) {
+        let after = self.token.accounts.get(account_id).unwrap_or(0);
+        if before == 0 && after > 0 {
+            self.nonzero_holders += 1;
+        } else if before > 0 && after == 0 {
+            self.nonzero_holders -= 1;
+        }
+    }
+
+    fn excluded_balance(&self, from_index: u64, limit: u64) -> Balance {
+        self.excluded_accounts
+            .as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|account_id| self.token.accounts.get(&account_id).unwrap_or(0))
+            .sum()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{env, testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{
+        current, metadata, owner, register_user, setup, user1, user2, TOTAL_SUPPLY,
+    };
+
+    fn exclude(contract: &mut Contract, context: &mut VMContextBuilder, account_id: AccountId) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.add_excluded_account(account_id);
+    }
+
+    #[test]
+    fn test_circulating_supply() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.ft_transfer(user2(), 2_000.into(), None);
+        assert_eq!(contract.ft_circulating_supply().0, TOTAL_SUPPLY);
+
+        exclude(&mut contract, &mut context, owner());
+        exclude(&mut contract, &mut context, user2());
+
+        assert_eq!(contract.ft_circulating_supply().0, 1_000);
+        assert_eq!(
+            contract.ft_excluded_balance(None, None).0,
+            TOTAL_SUPPLY - 1_000
+        );
+        assert_eq!(contract.ft_excluded_balance(Some(1), Some(1)).0, 2_000);
+    }
+
+    #[test]
+    fn test_locked_supply() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        assert_eq!(contract.ft_locked_supply().0, 0);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.create_vesting(user2(), 500.into(), 0.into(), 0.into(), 100.into());
+        assert_eq!(contract.ft_locked_supply().0, 0);
+
+        // the treasury and the vesting escrow
+        exclude(&mut contract, &mut context, owner());
+        exclude(&mut contract, &mut context, env::current_account_id());
+
+        assert_eq!(contract.ft_locked_supply().0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(
+            contract.ft_locked_supply().0 + contract.ft_circulating_supply().0,
+            TOTAL_SUPPLY
+        );
+    }
+
+    #[test]
+    fn test_supply_metrics() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.add_minter(owner());
+        contract.mint(user1(), 5_000.into(), None);
+        contract.redeem(1_000.into());
+        contract.create_vesting(user2(), 500.into(), 0.into(), 0.into(), 100.into());
+        exclude(&mut contract, &mut context, env::current_account_id());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.redeem(300.into());
+
+        let metrics = contract.supply_metrics();
+        assert_eq!(
+            metrics,
+            SupplyMetrics {
+                total: contract.ft_total_supply(),
+                circulating: contract.ft_circulating_supply(),
+                locked: contract.ft_locked_supply(),
+                burned: U128(1_300),
+                minted: U128(TOTAL_SUPPLY + 5_000),
+                reserved: U128(500),
+            }
+        );
+        assert_eq!(metrics.total.0, metrics.minted.0 - metrics.burned.0);
+        assert_eq!(metrics.locked.0, 500);
+    }
+
+    #[test]
+    fn test_set_excluded_accounts_replaces_set() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.ft_transfer(user2(), 2_000.into(), None);
+
+        contract.set_excluded_accounts(vec![owner(), user1(), owner()]);
+        assert_eq!(
+            contract.get_excluded_accounts(None, None),
+            vec![owner(), user1()]
+        );
+        assert_eq!(contract.ft_circulating_supply().0, 2_000);
+
+        contract.set_excluded_accounts(vec![user2()]);
+        assert_eq!(contract.get_excluded_accounts(None, None), vec![user2()]);
+        assert_eq!(contract.ft_circulating_supply().0, TOTAL_SUPPLY - 2_000);
+    }
+
+    #[test]
+    fn test_export_accounts() {
+        let (mut contract, mut context) = setup();
+        let accounts: Vec<AccountId> = (0..7)
+            .map(|i| format!("holder-{}.near", i).parse().unwrap())
+            .collect();
+        for (i, account_id) in accounts.iter().enumerate() {
+            register_user(&mut contract, &mut context, account_id.clone());
+            testing_env!(context
+                .predecessor_account_id(owner())
+                .attached_deposit(NearToken::from_yoctonear(1))
+                .build());
+            contract.ft_transfer(account_id.clone(), (i as u128 + 1).into(), None);
+        }
+
+        let mut exported = Vec::new();
+        let mut from_index = 0;
+        loop {
+            let page = contract.export_accounts(Some(from_index), Some(3));
+            if page.is_empty() {
+                break;
+            }
+            from_index += page.len() as u64;
+            exported.extend(page);
+        }
+
+        let mut expected = vec![(owner(), U128(TOTAL_SUPPLY - 28))];
+        expected.extend(
+            accounts
+                .into_iter()
+                .enumerate()
+                .map(|(i, account_id)| (account_id, U128(i as u128 + 1))),
+        );
+        assert_eq!(exported, expected);
+    }
+
+    #[test]
+    fn test_balance_sum() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.ft_transfer(user2(), 2_000.into(), None);
+
+        let unregistered: AccountId = "nobody.near".parse().unwrap();
+        assert_eq!(
+            contract
+                .ft_balance_sum(vec![user1(), unregistered, user2()])
+                .0,
+            3_000
+        );
+        assert_eq!(contract.ft_balance_sum(vec![]).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many accounts requested")]
+    fn test_balance_sum_above_limit() {
+        let (contract, _) = setup();
+
+        contract.ft_balance_sum(vec![user1(); MAX_ITERATED_ACCOUNTS as usize + 1]);
+    }
+
+    #[test]
+    fn test_ownership_bps() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), (TOTAL_SUPPLY / 10).into(), None);
+
+        assert_eq!(contract.ft_ownership_bps(user1()), 1_000);
+        assert_eq!(contract.ft_ownership_bps(owner()), 9_000);
+        assert_eq!(contract.ft_ownership_bps(user2()), 0);
+    }
+
+    #[test]
+    fn test_ownership_bps_of_max_supply() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id(current());
+        testing_env!(context.build());
+        let mut contract = Contract::new(owner(), u128::MAX.into(), metadata(), None, None);
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), (u128::MAX / 10).into(), None);
+
+        assert_eq!(contract.ft_ownership_bps(user1()), 1_000);
+        assert_eq!(contract.ft_ownership_bps(owner()), 9_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_set_excluded_accounts_only_owner() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_excluded_accounts(vec![user1()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many accounts for this operation; use paginated variant")]
+    fn test_circulating_supply_panics_above_limit() {
+        let (mut contract, mut context) = setup();
+
+        for i in 0..=MAX_ITERATED_ACCOUNTS {
+            exclude(
+                &mut contract,
+                &mut context,
+                format!("excluded-{}.near", i).parse().unwrap(),
+            );
+        }
+
+        // the paginated variant keeps working
+        assert_eq!(contract.ft_excluded_balance(Some(0), Some(50)).0, 0);
+        contract.ft_circulating_supply();
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many accounts for this operation; use paginated variant")]
+    fn test_excluded_balance_panics_on_large_page() {
+        let (contract, _) = setup();
+
+        contract.ft_excluded_balance(None, Some(MAX_ITERATED_ACCOUNTS + 1));
+    }
+
+    #[test]
+    fn test_nonzero_holder_count() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        assert_eq!(contract.nonzero_holder_count(), 1);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 10.into(), None);
+        assert_eq!(contract.nonzero_holder_count(), 2);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.redeem(10.into());
+        assert_eq!(contract.nonzero_holder_count(), 1);
+    }
+}
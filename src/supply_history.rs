@@ -0,0 +1,174 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near, require};
+
+use crate::errors::ERR_QUERY_TOO_LARGE;
+use crate::math::checked_add_supply;
+use crate::supply::MAX_ITERATED_ACCOUNTS;
+use crate::{Contract, ContractExt};
+
+/// Number of supply checkpoints kept. Once full, each new checkpoint overwrites the oldest.
+pub const MAX_SUPPLY_HISTORY_LEN: u64 = 1_000;
+
+#[near]
+impl Contract {
+    /// Returns a page of the kept `(timestamp_ns, total_supply)` checkpoints, oldest first.
+    /// A checkpoint is recorded at most once per block, after the last supply change of the
+    /// block.
+    pub fn supply_history(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<(U64, U128)> {
+        let limit = limit.unwrap_or(MAX_ITERATED_ACCOUNTS);
+        require!(limit <= MAX_ITERATED_ACCOUNTS, ERR_QUERY_TOO_LARGE);
+        let len = self.supply_history.len();
+        let oldest = self.supply_checkpoints - len;
+        let from_index = from_index.unwrap_or(0).min(len);
+        (from_index..len.min(from_index.saturating_add(limit)))
+            .map(|index| {
+                let (timestamp, supply) = self
+                    .supply_history
+                    .get((oldest + index) % MAX_SUPPLY_HISTORY_LEN)
+                    .unwrap();
+                (timestamp.into(), supply.into())
+            })
+            .collect()
+    }
+
+    /// Returns the number of kept supply checkpoints.
+    pub fn supply_history_len(&self) -> u64 {
+        self.supply_history.len()
+    }
+}
+
+impl Contract {
+    /// Counts `amount` newly minted tokens and records the new total supply.
+    pub(crate) fn record_minted(&mut self, amount: Balance) {
+        self.total_minted = checked_add_supply(self.total_minted, amount);
+        self.record_supply_checkpoint();
+    }
+
+    /// Counts `amount` burned tokens and records the new total supply.
+    pub(crate) fn record_burned(&mut self, amount: Balance) {
+        self.total_burned = checked_add_supply(self.total_burned, amount);
+        self.record_supply_checkpoint();
+    }
+
+    /// Records the current total supply. A second change in the same block updates the
+    /// checkpoint of the block instead of adding one.
+    pub(crate) fn record_supply_checkpoint(&mut self) {
+        let checkpoint = (env::block_timestamp(), self.token.total_supply);
+        if self.supply_checkpoints > 0 {
+            let last = (self.supply_checkpoints - 1) % MAX_SUPPLY_HISTORY_LEN;
+            if self
+                .supply_history
+                .get(last)
+                .is_some_and(|(timestamp, _)| timestamp == checkpoint.0)
+            {
+                self.supply_history.replace(last, &checkpoint);
+                return;
+            }
+        }
+        if self.supply_history.len() < MAX_SUPPLY_HISTORY_LEN {
+            self.supply_history.push(&checkpoint);
+        } else {
+            self.supply_history.replace(
+                self.supply_checkpoints % MAX_SUPPLY_HISTORY_LEN,
+                &checkpoint,
+            );
+        }
+        self.supply_checkpoints += 1;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, TOTAL_SUPPLY};
+
+    fn as_owner_at(context: &mut VMContextBuilder, timestamp: u64) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+    }
+
+    fn history(contract: &Contract) -> Vec<(u64, u128)> {
+        contract
+            .supply_history(None, None)
+            .into_iter()
+            .map(|(timestamp, supply)| (timestamp.0, supply.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_checkpoints_on_mint_and_burn() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        assert_eq!(history(&contract), vec![(0, TOTAL_SUPPLY)]);
+
+        as_owner_at(&mut context, 10);
+        contract.add_minter(owner());
+        contract.mint(user1(), 500.into(), None);
+
+        as_owner_at(&mut context, 20);
+        contract.redeem(200.into());
+
+        assert_eq!(
+            history(&contract),
+            vec![
+                (0, TOTAL_SUPPLY),
+                (10, TOTAL_SUPPLY + 500),
+                (20, TOTAL_SUPPLY + 300)
+            ]
+        );
+        assert_eq!(
+            contract.supply_history(Some(1), Some(1)),
+            vec![(U64(10), U128(TOTAL_SUPPLY + 500))]
+        );
+    }
+
+    #[test]
+    fn test_one_checkpoint_per_block() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        as_owner_at(&mut context, 10);
+        contract.add_minter(owner());
+        contract.mint(user1(), 500.into(), None);
+        contract.mint(user1(), 100.into(), None);
+
+        assert_eq!(
+            history(&contract),
+            vec![(0, TOTAL_SUPPLY), (10, TOTAL_SUPPLY + 600)]
+        );
+    }
+
+    #[test]
+    fn test_history_keeps_latest_checkpoints() {
+        let (mut contract, mut context) = setup();
+        // fill the history up to one checkpoint per timestamp 0..MAX_SUPPLY_HISTORY_LEN
+        for timestamp in 1..MAX_SUPPLY_HISTORY_LEN {
+            contract.supply_history.push(&(timestamp, TOTAL_SUPPLY));
+        }
+        contract.supply_checkpoints = MAX_SUPPLY_HISTORY_LEN;
+
+        as_owner_at(&mut context, MAX_SUPPLY_HISTORY_LEN);
+        contract.record_supply_checkpoint();
+        as_owner_at(&mut context, MAX_SUPPLY_HISTORY_LEN + 1);
+        contract.record_supply_checkpoint();
+
+        assert_eq!(contract.supply_history_len(), MAX_SUPPLY_HISTORY_LEN);
+        let oldest = contract.supply_history(None, Some(1));
+        assert_eq!(oldest[0].0 .0, 2);
+        let latest = contract.supply_history(Some(MAX_SUPPLY_HISTORY_LEN - 2), None);
+        assert_eq!(
+            latest,
+            vec![
+                (U64(MAX_SUPPLY_HISTORY_LEN), U128(TOTAL_SUPPLY)),
+                (U64(MAX_SUPPLY_HISTORY_LEN + 1), U128(TOTAL_SUPPLY))
+            ]
+        );
+    }
+}
@@ -0,0 +1,1163 @@
+use near_contract_standards::fungible_token::receiver::ext_ft_receiver;
+use near_contract_standards::fungible_token::resolver::ext_ft_resolver;
+use near_contract_standards::fungible_token::Balance;
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::{self, Value};
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, log, near, require, AccountId, Gas, NearToken, Promise,
+    PromiseError, PromiseOrValue, PromiseResult,
+};
+
+use crate::errors::{
+    ERR_INVALID_MAX_REFUND, ERR_MALFORMED_MSG, ERR_MAX_REFUND_TOO_HIGH, ERR_MORE_GAS_REQUIRED,
+    ERR_REQUIRES_DEPOSIT, ERR_RESOLVE_GAS_OUT_OF_RANGE, ERR_SELF_TRANSFER,
+    ERR_TRANSFER_CALL_RECEIVER_NOT_APPROVED, ERR_VOLUME_CAP_EXCEEDED, ERR_ZERO_AMOUNT,
+};
+use crate::preflight::TransferCheck;
+use crate::receiver_policy::UnregisteredReceiverPolicy;
+use crate::supply::paginate_accounts;
+use crate::{Contract, ContractExt};
+
+/// Gas attached to the receiver's `storage_balance_of`.
+const GAS_FOR_STORAGE_CHECK: Gas = Gas::from_tgas(5);
+/// Gas reserved for `on_receiver_storage_checked`, covering the receiver's `storage_deposit`,
+/// `on_sender_registered` and `ft_resolve_transfer`. The gas left is forwarded to
+/// `ft_on_transfer`.
+const GAS_FOR_STORAGE_CHECK_CALLBACK: Gas = Gas::from_tgas(30);
+/// Gas attached to the receiver's `storage_deposit`.
+const GAS_FOR_RECEIVER_STORAGE_DEPOSIT: Gas = Gas::from_tgas(10);
+/// Gas reserved for `on_sender_registered` itself, on top of the gas it forwards.
+const GAS_FOR_REGISTRATION_CALLBACK: Gas = Gas::from_tgas(10);
+/// Default gas attached to `ft_resolve_transfer`, the same as the SDK implementation.
+pub const DEFAULT_GAS_FOR_RESOLVE_TRANSFER: Gas = Gas::from_tgas(5);
+/// Bounds of the gas the owner can attach to `ft_resolve_transfer`.
+pub const MIN_GAS_FOR_RESOLVE_TRANSFER: Gas = Gas::from_tgas(5);
+pub const MAX_GAS_FOR_RESOLVE_TRANSFER: Gas = Gas::from_tgas(100);
+/// Gas kept by `ft_transfer_call` for itself on top of the resolve gas. The rest of the
+/// prepaid gas goes to the receiver's `ft_on_transfer`.
+const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas::from_tgas(25);
+/// Minimum gas for `ft_transfer_call_with_storage`, leaving the receiver's `ft_on_transfer`
+/// at least 30 TGas.
+pub const GAS_FOR_TRANSFER_CALL_WITH_STORAGE: Gas = Gas::from_tgas(80);
+
+/// NEP-145 methods of the receiver contract used to register the sender there.
+#[allow(dead_code)]
+#[ext_contract(ext_receiver_storage)]
+trait ReceiverStorage {
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance;
+}
+
+/// Structured `ft_transfer_call` payload recognized by the token.
+#[derive(Debug, PartialEq)]
+pub struct TransferCallMsg {
+    /// Upper bound of the amount the receiver may refund.
+    pub max_refund: U128,
+}
+
+/// Validates a JSON `msg` carrying a `max_refund` field before the cross-contract call is made.
+///
+/// Plain-text messages and JSON objects without `max_refund` are passed through untouched and
+/// `None` is returned. A message that looks like JSON but can't be parsed, or whose `max_refund`
+/// isn't a valid amount not greater than `amount`, panics so the transfer never happens.
+pub fn parse_transfer_call_msg(msg: &str, amount: Balance) -> Option<TransferCallMsg> {
+    if !msg.trim_start().starts_with('{') {
+        return None;
+    }
+    let value: Value =
+        serde_json::from_str(msg).unwrap_or_else(|_| env::panic_str(ERR_MALFORMED_MSG));
+    let max_refund = value.get("max_refund")?;
+    let max_refund: U128 = serde_json::from_value(max_refund.clone())
+        .unwrap_or_else(|_| env::panic_str(ERR_INVALID_MAX_REFUND));
+    require!(max_refund.0 <= amount, ERR_MAX_REFUND_TOO_HIGH);
+    Some(TransferCallMsg { max_refund })
+}
+
+#[near]
+impl Contract {
+    /// Sets the gas attached to `ft_resolve_transfer` by the transfer calls, for receivers
+    /// whose refunds need a heavier resolution.
+    #[payable]
+    pub fn set_resolve_gas(&mut self, resolve_gas: Gas) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            resolve_gas >= MIN_GAS_FOR_RESOLVE_TRANSFER
+                && resolve_gas <= MAX_GAS_FOR_RESOLVE_TRANSFER,
+            ERR_RESOLVE_GAS_OUT_OF_RANGE
+        );
+        self.resolve_gas = resolve_gas;
+    }
+
+    pub fn get_resolve_gas(&self) -> Gas {
+        self.resolve_gas
+    }
+
+    /// Lets `ft_transfer_call` accept a zero amount, in which case nothing moves and only the
+    /// receiver's `ft_on_transfer` is called, e.g. to notify it. Zero amounts panic by default.
+    #[payable]
+    pub fn set_allow_zero_transfers(&mut self, allow: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.allow_zero_transfers = allow;
+    }
+
+    pub fn is_zero_transfers_allowed(&self) -> bool {
+        self.allow_zero_transfers
+    }
+
+    /// Chooses what happens to the refund of a transfer call whose sender unregistered before
+    /// the resolution: burned, as in the SDK (the default), or held by the contract for the
+    /// sender until it registers again and calls `claim_pending`.
+    #[payable]
+    pub fn set_burn_unresolved_refunds(&mut self, burn: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.burn_unresolved_refunds = burn;
+    }
+
+    pub fn is_burn_unresolved_refunds(&self) -> bool {
+        self.burn_unresolved_refunds
+    }
+
+    /// Restricts `ft_transfer_call` to the receivers approved with
+    /// `add_transfer_call_receiver`, or lifts the restriction. Plain transfers are unaffected.
+    #[payable]
+    pub fn set_restrict_transfer_call(&mut self, restrict: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.restrict_transfer_call = restrict;
+    }
+
+    pub fn is_transfer_call_restricted(&self) -> bool {
+        self.restrict_transfer_call
+    }
+
+    #[payable]
+    pub fn add_transfer_call_receiver(&mut self, receiver_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.transfer_call_receivers.insert(&receiver_id)
+    }
+
+    #[payable]
+    pub fn remove_transfer_call_receiver(&mut self, receiver_id: AccountId) -> bool {
+        assert_one_yocto();
+        self.assert_owner();
+        self.transfer_call_receivers.remove(&receiver_id)
+    }
+
+    pub fn get_transfer_call_receivers(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        paginate_accounts(&self.transfer_call_receivers, from_index, limit)
+    }
+
+    /// `ft_transfer_call` for receivers that need the sender to be registered with them first.
+    ///
+    /// The receiver's `storage_balance_of` is checked for the sender and, if the sender isn't
+    /// registered there, the receiver's `storage_deposit` is called before `ft_on_transfer`.
+    /// Attach 1 yoctoNEAR plus the receiver's minimum storage balance: the registration is
+    /// funded from it and, if the sender is already registered, everything but the yoctoNEAR
+    /// is refunded. Requires at least `GAS_FOR_TRANSFER_CALL_WITH_STORAGE` (80 TGas).
+    #[payable]
+    pub fn ft_transfer_call_with_storage(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> Promise {
+        let one_yocto = NearToken::from_yoctonear(1);
+        require!(env::attached_deposit() >= one_yocto, ERR_REQUIRES_DEPOSIT);
+        require!(
+            env::prepaid_gas() >= GAS_FOR_TRANSFER_CALL_WITH_STORAGE,
+            ERR_MORE_GAS_REQUIRED
+        );
+        let sender_id = env::predecessor_account_id();
+        require!(sender_id != receiver_id, ERR_SELF_TRANSFER);
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        require!(
+            self.token.accounts.contains_key(&receiver_id),
+            format!("The account {} is not registered", receiver_id)
+        );
+        self.assert_transfer_call_receiver_approved(&receiver_id);
+        self.assert_not_paused();
+        self.assert_not_frozen(&sender_id);
+        self.assert_not_self_locked(&sender_id);
+        self.assert_receiver_allowed(&sender_id, &receiver_id);
+        parse_transfer_call_msg(&msg, amount.0);
+        self.assert_transfer_precision(amount.0);
+        self.assert_unlocked(&sender_id, amount.0);
+        self.assert_min_remaining(&sender_id, amount.0);
+        require!(!self.exceeds_volume_cap(amount.0), ERR_VOLUME_CAP_EXCEEDED);
+        require!(
+            self.token.accounts.get(&sender_id).unwrap_or(0) >= amount.0,
+            "The account doesn't have enough balance"
+        );
+
+        let storage_deposit = env::attached_deposit().saturating_sub(one_yocto);
+        ext_receiver_storage::ext(receiver_id.clone())
+            .with_static_gas(GAS_FOR_STORAGE_CHECK)
+            .storage_balance_of(sender_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_STORAGE_CHECK_CALLBACK)
+                    .on_receiver_storage_checked(
+                        sender_id,
+                        receiver_id,
+                        amount,
+                        memo,
+                        msg,
+                        storage_deposit,
+                    ),
+            )
+    }
+
+    /// Registers the sender with the receiver if needed, then makes the transfer call, charged
+    /// like `ft_transfer_call`. The guards of the transfer run again, as the accounts or the
+    /// settings may have changed while the receiver was queried: if one fails, nothing is
+    /// transferred and the storage deposit is refunded. This callback never panics once the
+    /// storage check has run.
+    #[private]
+    #[allow(clippy::too_many_arguments)]
+    pub fn on_receiver_storage_checked(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+        storage_deposit: NearToken,
+        #[callback_result] storage_balance: Result<Option<StorageBalance>, PromiseError>,
+    ) -> PromiseOrValue<U128> {
+        // A receiver without storage management fails the check: nothing to register then.
+        if !matches!(storage_balance, Ok(None)) {
+            if !storage_deposit.is_zero() {
+                Promise::new(sender_id.clone()).transfer(storage_deposit);
+            }
+            return self.internal_checked_transfer_call(sender_id, receiver_id, amount, memo, msg);
+        }
+        if self.is_transfer_call_blocked(&sender_id, &receiver_id, amount.0) {
+            if !storage_deposit.is_zero() {
+                Promise::new(sender_id).transfer(storage_deposit);
+            }
+            return PromiseOrValue::Value(U128(0));
+        }
+        ext_receiver_storage::ext(receiver_id.clone())
+            .with_attached_deposit(storage_deposit)
+            .with_static_gas(GAS_FOR_RECEIVER_STORAGE_DEPOSIT)
+            .with_unused_gas_weight(0)
+            .storage_deposit(Some(sender_id.clone()), Some(true))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_REGISTRATION_CALLBACK)
+                    .on_sender_registered(
+                        sender_id,
+                        receiver_id,
+                        amount,
+                        memo,
+                        msg,
+                        storage_deposit,
+                    ),
+            )
+            .into()
+    }
+
+    /// Makes the transfer call once the receiver's `storage_deposit` registered the sender.
+    /// If the registration failed, the deposit came back to this contract: it is returned to
+    /// the sender and nothing is transferred.
+    #[private]
+    pub fn on_sender_registered(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+        storage_deposit: NearToken,
+    ) -> PromiseOrValue<U128> {
+        if matches!(env::promise_result(0), PromiseResult::Failed) {
+            log!(
+                "Transfer call from {} to {} not made: the registration with the receiver failed",
+                sender_id,
+                receiver_id
+            );
+            if !storage_deposit.is_zero() {
+                Promise::new(sender_id).transfer(storage_deposit);
+            }
+            return PromiseOrValue::Value(U128(0));
+        }
+        self.internal_checked_transfer_call(sender_id, receiver_id, amount, memo, msg)
+    }
+}
+
+impl Contract {
+    /// Whether a guard of a transfer call of `amount` from `sender_id` to `receiver_id`
+    /// fails, which is logged, as checked by the callbacks of `ft_transfer_call_with_storage`
+    /// instead of panicking.
+    fn is_transfer_call_blocked(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> bool {
+        // transfer calls always need a registered receiver
+        let credited = self
+            .compute_splits(sender_id, receiver_id, amount)
+            .to_receiver;
+        let failed_guard = match self.first_failed_transfer_guard(
+            sender_id,
+            receiver_id,
+            amount,
+            credited,
+            UnregisteredReceiverPolicy::Reject,
+        ) {
+            TransferCheck::Ok if !self.is_transfer_call_receiver_approved(receiver_id) => {
+                ERR_TRANSFER_CALL_RECEIVER_NOT_APPROVED.to_string()
+            }
+            TransferCheck::Ok => return false,
+            check => format!("{:?}", check),
+        };
+        log!(
+            "Transfer call from {} to {} not made: {}",
+            sender_id,
+            receiver_id,
+            failed_guard
+        );
+        true
+    }
+
+    /// `internal_transfer_call` for the callbacks of `ft_transfer_call_with_storage`, which
+    /// must not panic: returns 0 without transferring anything if a guard fails.
+    fn internal_checked_transfer_call(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        if self.is_transfer_call_blocked(&sender_id, &receiver_id, amount.0) {
+            return PromiseOrValue::Value(U128(0));
+        }
+        let amount = U128(self.internal_transfer(&sender_id, &receiver_id, amount.0, memo));
+        ext_ft_receiver::ext(receiver_id.clone())
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                ext_ft_resolver::ext(env::current_account_id())
+                    .with_static_gas(self.resolve_gas)
+                    .with_unused_gas_weight(0)
+                    .ft_resolve_transfer(sender_id, receiver_id, amount),
+            )
+            .into()
+    }
+
+    /// Panics if transfer calls are restricted and `receiver_id` isn't approved.
+    pub(crate) fn assert_transfer_call_receiver_approved(&self, receiver_id: &AccountId) {
+        require!(
+            self.is_transfer_call_receiver_approved(receiver_id),
+            ERR_TRANSFER_CALL_RECEIVER_NOT_APPROVED
+        );
+    }
+
+    fn is_transfer_call_receiver_approved(&self, receiver_id: &AccountId) -> bool {
+        !self.restrict_transfer_call || self.transfer_call_receivers.contains(receiver_id)
+    }
+
+    /// Moves `amount` to `receiver_id` like `ft_transfer` and calls its `ft_on_transfer`
+    /// with the credited amount, resolved with the configured resolve gas. Mirrors the SDK
+    /// implementation otherwise. A zero amount, when allowed, only calls the receiver.
+    pub(crate) fn internal_transfer_call(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let reserved_gas = GAS_FOR_FT_TRANSFER_CALL.saturating_add(self.resolve_gas);
+        require!(env::prepaid_gas() > reserved_gas, ERR_MORE_GAS_REQUIRED);
+        let amount = if amount.0 > 0 {
+            U128(self.internal_transfer(&sender_id, &receiver_id, amount.0, memo))
+        } else {
+            require!(sender_id != receiver_id, ERR_SELF_TRANSFER);
+            self.assert_not_paused();
+            self.assert_not_frozen(&sender_id);
+            amount
+        };
+        ext_ft_receiver::ext(receiver_id.clone())
+            .with_static_gas(env::prepaid_gas().saturating_sub(reserved_gas))
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                ext_ft_resolver::ext(env::current_account_id())
+                    .with_static_gas(self.resolve_gas)
+                    .ft_resolve_transfer(sender_id, receiver_id, amount),
+            )
+            .into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_contract_standards::fungible_token::FungibleTokenResolver;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    use super::*;
+    use crate::tests::{current, owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    /// A function call scheduled by the contract.
+    struct Call {
+        receiver_id: AccountId,
+        method_name: String,
+        args: String,
+        deposit: NearToken,
+        gas: Gas,
+        receipt_index: u64,
+        /// Receipts the call waits for.
+        dependencies: Vec<u64>,
+    }
+
+    /// Returns the function calls scheduled by the contract, in creation order.
+    fn scheduled_calls() -> Vec<Call> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id.clone();
+                let receipt_indices = receipt.receipt_indices.clone();
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::FunctionCallWeight {
+                            receipt_index,
+                            method_name,
+                            args,
+                            attached_deposit,
+                            prepaid_gas,
+                            ..
+                        } => Some(Call {
+                            receiver_id: receiver_id.clone(),
+                            method_name: String::from_utf8(method_name).unwrap(),
+                            args: String::from_utf8(args).unwrap(),
+                            deposit: attached_deposit,
+                            gas: prepaid_gas,
+                            receipt_index,
+                            dependencies: receipt_indices.clone(),
+                        }),
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    fn storage_deposit() -> NearToken {
+        NearToken::from_millinear(2)
+    }
+
+    fn transfer_call(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .prepaid_gas(Gas::from_tgas(300))
+            .build());
+        contract.ft_transfer_call(user1(), 100.into(), None, "".to_string());
+    }
+
+    fn resolve_gas() -> Gas {
+        scheduled_calls()
+            .into_iter()
+            .find(|call| call.method_name == "ft_resolve_transfer")
+            .unwrap()
+            .gas
+    }
+
+    fn zero_transfer_call(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .prepaid_gas(Gas::from_tgas(300))
+            .build());
+        contract.ft_transfer_call(user1(), 0.into(), None, "ping".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount should be a positive number")]
+    fn test_zero_transfer_call_panics_by_default() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        assert!(!contract.is_zero_transfers_allowed());
+
+        zero_transfer_call(&mut contract, &mut context);
+    }
+
+    #[test]
+    fn test_zero_transfer_call_only_notifies_when_allowed() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_allow_zero_transfers(true);
+
+        zero_transfer_call(&mut contract, &mut context);
+
+        let methods: Vec<String> = scheduled_calls()
+            .into_iter()
+            .map(|call| call.method_name)
+            .collect();
+        assert_eq!(methods, vec!["ft_on_transfer", "ft_resolve_transfer"]);
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+    }
+
+    #[test]
+    fn test_zero_transfer_call_records_nothing() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_allow_zero_transfers(true);
+        contract.set_transfer_observer(Some(accounts(5)));
+
+        zero_transfer_call(&mut contract, &mut context);
+
+        assert!(scheduled_calls()
+            .iter()
+            .all(|call| call.method_name != "on_ft_transfer"));
+        assert_eq!(contract.get_epoch_volume().0, 0);
+    }
+
+    #[test]
+    fn test_refund_released_from_epoch_volume() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        transfer_call(&mut contract, &mut context);
+        assert_eq!(contract.get_epoch_volume().0, 100);
+
+        testing_env!(
+            context.predecessor_account_id(current()).build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"40\"".to_vec())],
+        );
+        let used = contract.ft_resolve_transfer(owner(), user1(), 100.into());
+
+        assert_eq!(used.0, 60);
+        assert_eq!(contract.ft_balance_of(user1()).0, 60);
+        assert_eq!(contract.get_epoch_volume().0, 60);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount should be a positive number")]
+    fn test_zero_ft_transfer_panics_even_when_allowed() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_allow_zero_transfers(true);
+
+        contract.ft_transfer(user1(), 0.into(), None);
+    }
+
+    /// Sends 1_000 from user1 to user2 with `ft_transfer_call`, unregisters user1 and
+    /// resolves the call as if user2's `ft_on_transfer` had failed.
+    fn resolve_after_sender_unregistered(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+    ) -> U128 {
+        register_user(contract, context, user1());
+        register_user(contract, context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .prepaid_gas(Gas::from_tgas(300))
+            .build());
+        let _ = contract.ft_transfer_call(user2(), 1_000.into(), None, "".to_string());
+        assert!(contract.storage_unregister(None));
+
+        testing_env!(
+            context.predecessor_account_id(current()).build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.ft_resolve_transfer(user1(), user2(), 1_000.into())
+    }
+
+    #[test]
+    fn test_unresolved_refund_burned_by_default() {
+        let (mut contract, mut context) = setup();
+        assert!(contract.is_burn_unresolved_refunds());
+
+        let used = resolve_after_sender_unregistered(&mut contract, &mut context);
+
+        assert_eq!(used.0, 1_000);
+        assert_eq!(contract.ft_balance_of(user2()).0, 0);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.supply_metrics().burned.0, 1_000);
+        assert_eq!(contract.get_pending_claim(user1()).0, 0);
+    }
+
+    #[test]
+    fn test_unresolved_refund_held_for_sender() {
+        let (mut contract, mut context) = setup();
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_burn_unresolved_refunds(false);
+
+        let used = resolve_after_sender_unregistered(&mut contract, &mut context);
+
+        assert_eq!(used.0, 0);
+        assert_eq!(contract.ft_balance_of(user2()).0, 0);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.get_pending_claim(user1()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(current()).0, 1_000);
+        // holding the refund neither burns nor mints anything
+        let metrics = contract.supply_metrics();
+        assert_eq!(metrics.minted.0, TOTAL_SUPPLY);
+        assert_eq!(metrics.burned.0, 0);
+        assert!(!get_logs().iter().any(
+            |log| log.contains(r#""event":"ft_burn""#) || log.contains(r#""event":"ft_mint""#)
+        ));
+
+        // the sender gets its whole refund back once registered again
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert_eq!(contract.claim_pending().0, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+    }
+
+    fn restrict_transfer_call(contract: &mut Contract, context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_restrict_transfer_call(true);
+        contract.add_transfer_call_receiver(user2());
+    }
+
+    #[test]
+    fn test_transfer_call_to_approved_receiver() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user2());
+        restrict_transfer_call(&mut contract, &mut context);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .prepaid_gas(Gas::from_tgas(300))
+            .build());
+        let _ = contract.ft_transfer_call(user2(), 100.into(), None, "".to_string());
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 100);
+        assert_eq!(
+            contract.get_transfer_call_receivers(None, None),
+            vec![user2()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver is not an approved transfer call receiver")]
+    fn test_transfer_call_to_unapproved_receiver() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        restrict_transfer_call(&mut contract, &mut context);
+
+        transfer_call(&mut contract, &mut context);
+    }
+
+    #[test]
+    fn test_transfer_call_receivers_ignored_without_restriction() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        restrict_transfer_call(&mut contract, &mut context);
+        contract.set_restrict_transfer_call(false);
+
+        transfer_call(&mut contract, &mut context);
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+
+        // plain transfers are never restricted
+        restrict_transfer_call(&mut contract, &mut context);
+        contract.ft_transfer(user1(), 100.into(), None);
+        assert_eq!(contract.ft_balance_of(user1()).0, 200);
+    }
+
+    #[test]
+    fn test_transfer_call_uses_default_resolve_gas() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        transfer_call(&mut contract, &mut context);
+
+        assert_eq!(contract.get_resolve_gas(), DEFAULT_GAS_FOR_RESOLVE_TRANSFER);
+        assert_eq!(resolve_gas(), DEFAULT_GAS_FOR_RESOLVE_TRANSFER);
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    fn test_transfer_call_uses_configured_resolve_gas() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_resolve_gas(Gas::from_tgas(20));
+
+        transfer_call(&mut contract, &mut context);
+
+        assert_eq!(resolve_gas(), Gas::from_tgas(20));
+        let on_transfer = scheduled_calls()
+            .into_iter()
+            .find(|call| call.method_name == "ft_on_transfer")
+            .unwrap();
+        assert_eq!(on_transfer.gas, Gas::from_tgas(300 - 25 - 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "Resolve gas is out of the allowed range")]
+    fn test_set_resolve_gas_below_minimum() {
+        let (mut contract, mut context) = setup();
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_resolve_gas(Gas::from_tgas(1));
+    }
+
+    #[test]
+    fn test_parse_plain_msg() {
+        assert_eq!(parse_transfer_call_msg("", 100), None);
+        assert_eq!(parse_transfer_call_msg("take-my-money", 100), None);
+        assert_eq!(parse_transfer_call_msg(r#"{"action":"stake"}"#, 100), None);
+    }
+
+    #[test]
+    fn test_parse_structured_msg() {
+        assert_eq!(
+            parse_transfer_call_msg(r#"{"max_refund":"40","action":"stake"}"#, 100),
+            Some(TransferCallMsg {
+                max_refund: U128(40)
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Malformed transfer call msg")]
+    fn test_parse_malformed_json() {
+        parse_transfer_call_msg(r#"{"max_refund":"40""#, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid max_refund in transfer call msg")]
+    fn test_parse_invalid_max_refund() {
+        parse_transfer_call_msg(r#"{"max_refund":-1}"#, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_refund can't exceed the transferred amount")]
+    fn test_parse_max_refund_above_amount() {
+        parse_transfer_call_msg(r#"{"max_refund":"101"}"#, 100);
+    }
+
+    #[test]
+    fn test_transfer_call_with_structured_msg() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        let transfer_amount = TOTAL_SUPPLY / 10;
+        contract.ft_transfer_call(
+            user1(),
+            transfer_amount.into(),
+            None,
+            format!(r#"{{"max_refund":"{}"}}"#, transfer_amount / 2),
+        );
+
+        assert_eq!(contract.ft_balance_of(user1()).0, transfer_amount);
+    }
+
+    #[test]
+    #[should_panic(expected = "Malformed transfer call msg")]
+    fn test_transfer_call_with_malformed_msg() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer_call(
+            user1(),
+            (TOTAL_SUPPLY / 10).into(),
+            None,
+            r#"{"max_refund":"#.to_string(),
+        );
+    }
+
+    #[test]
+    fn test_transfer_call_with_storage_checks_receiver_first() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(storage_deposit().saturating_add(NearToken::from_yoctonear(1)))
+            .prepaid_gas(Gas::from_tgas(100))
+            .build());
+        let _ = contract.ft_transfer_call_with_storage(user1(), 100.into(), None, "".to_string());
+
+        let calls = scheduled_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].receiver_id, user1());
+        assert_eq!(calls[0].method_name, "storage_balance_of");
+        assert_eq!(calls[1].receiver_id, current());
+        assert_eq!(calls[1].method_name, "on_receiver_storage_checked");
+        assert_eq!(calls[1].dependencies, vec![calls[0].receipt_index]);
+        // nothing moves before the check resolves
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+    }
+
+    #[test]
+    fn test_transfer_call_with_storage_registers_sender_first() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context.predecessor_account_id(current()).build());
+        let _ = contract.on_receiver_storage_checked(
+            owner(),
+            user1(),
+            100.into(),
+            None,
+            "".to_string(),
+            storage_deposit(),
+            Ok(None),
+        );
+
+        let calls = scheduled_calls();
+        let methods: Vec<&str> = calls.iter().map(|call| call.method_name.as_str()).collect();
+        assert_eq!(methods, vec!["storage_deposit", "on_sender_registered"]);
+        assert_eq!(calls[0].receiver_id, user1());
+        assert_eq!(calls[0].deposit, storage_deposit());
+        assert_eq!(calls[1].receiver_id, current());
+        assert_eq!(calls[1].dependencies, vec![calls[0].receipt_index]);
+        // nothing moves before the registration resolves
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+    }
+
+    /// Runs `on_sender_registered` for a 100 tokens transfer call from the owner to `user1`
+    /// with the given result of the receiver's `storage_deposit`.
+    fn sender_registered(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        registration: PromiseResult,
+    ) {
+        testing_env!(
+            context.predecessor_account_id(current()).build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![registration],
+        );
+        let _ = contract.on_sender_registered(
+            owner(),
+            user1(),
+            100.into(),
+            None,
+            "".to_string(),
+            storage_deposit(),
+        );
+    }
+
+    #[test]
+    fn test_transfer_call_after_registration() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        sender_registered(
+            &mut contract,
+            &mut context,
+            PromiseResult::Successful(vec![]),
+        );
+
+        let calls = scheduled_calls();
+        let methods: Vec<&str> = calls.iter().map(|call| call.method_name.as_str()).collect();
+        assert_eq!(methods, vec!["ft_on_transfer", "ft_resolve_transfer"]);
+        assert_eq!(calls[1].dependencies, vec![calls[0].receipt_index]);
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    #[test]
+    fn test_failed_registration_refunds_the_sender() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        sender_registered(&mut contract, &mut context, PromiseResult::Failed);
+
+        assert!(scheduled_calls().is_empty());
+        let refund = get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == owner())
+            .flat_map(|receipt| receipt.actions)
+            .find_map(|action| match action {
+                MockAction::Transfer { deposit, .. } => Some(deposit),
+                _ => None,
+            });
+        assert_eq!(refund, Some(storage_deposit()));
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_transfer_call_with_storage_skips_registered_sender() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context.predecessor_account_id(current()).build());
+        let _ = contract.on_receiver_storage_checked(
+            owner(),
+            user1(),
+            100.into(),
+            None,
+            "".to_string(),
+            storage_deposit(),
+            Ok(Some(StorageBalance {
+                total: storage_deposit(),
+                available: NearToken::from_near(0),
+            })),
+        );
+
+        let methods: Vec<String> = scheduled_calls()
+            .into_iter()
+            .map(|call| call.method_name)
+            .collect();
+        assert_eq!(methods, vec!["ft_on_transfer", "ft_resolve_transfer"]);
+        let refund = get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == owner())
+            .flat_map(|receipt| receipt.actions)
+            .find_map(|action| match action {
+                MockAction::Transfer { deposit, .. } => Some(deposit),
+                _ => None,
+            });
+        assert_eq!(refund, Some(storage_deposit()));
+        assert_eq!(contract.ft_balance_of(user1()).0, 100);
+    }
+
+    /// Runs the storage check callback of a 100 tokens transfer call from the owner to
+    /// `user1` and returns the storage deposit refunded to the owner, if any.
+    fn storage_checked(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+    ) -> Option<NearToken> {
+        testing_env!(context
+            .predecessor_account_id(current())
+            .attached_deposit(NearToken::from_yoctonear(0))
+            .build());
+        let _ = contract.on_receiver_storage_checked(
+            owner(),
+            user1(),
+            100.into(),
+            None,
+            "".to_string(),
+            storage_deposit(),
+            Ok(None),
+        );
+        get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == owner())
+            .flat_map(|receipt| receipt.actions)
+            .find_map(|action| match action {
+                MockAction::Transfer { deposit, .. } => Some(deposit),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_storage_checked_refunds_when_paused_meanwhile() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_paused(true, None);
+
+        assert_eq!(
+            storage_checked(&mut contract, &mut context),
+            Some(storage_deposit())
+        );
+        assert!(scheduled_calls().is_empty());
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+    }
+
+    #[test]
+    fn test_storage_checked_refunds_when_sender_self_locked_meanwhile() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_self_lock(true);
+
+        assert_eq!(
+            storage_checked(&mut contract, &mut context),
+            Some(storage_deposit())
+        );
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_storage_checked_refunds_when_receiver_unregistered_meanwhile() {
+        let (mut contract, mut context) = setup();
+
+        assert_eq!(
+            storage_checked(&mut contract, &mut context),
+            Some(storage_deposit())
+        );
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY);
+    }
+
+    fn transfer_call_with_storage(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        amount: u128,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(storage_deposit().saturating_add(NearToken::from_yoctonear(1)))
+            .prepaid_gas(Gas::from_tgas(100))
+            .build());
+        let _ =
+            contract.ft_transfer_call_with_storage(user1(), amount.into(), None, "".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount should be a positive number")]
+    fn test_transfer_call_with_storage_zero_amount() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        transfer_call_with_storage(&mut contract, &mut context, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_transfer_call_with_storage_unregistered_receiver() {
+        let (mut contract, mut context) = setup();
+
+        transfer_call_with_storage(&mut contract, &mut context, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "More gas is required")]
+    fn test_transfer_call_with_storage_requires_gas() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .prepaid_gas(Gas::from_tgas(50))
+            .build());
+        let _ = contract.ft_transfer_call_with_storage(user1(), 100.into(), None, "".to_string());
+    }
+
+    /// Funds user1 with 10_000 tokens, registers user2 and sets a 1% transfer fee, collected
+    /// by the owner.
+    fn setup_with_fee() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(user1(), 10_000.into(), None);
+        contract.set_transfer_fee(100);
+        (contract, context)
+    }
+
+    fn on_transfer_args() -> String {
+        scheduled_calls()
+            .into_iter()
+            .find(|call| call.method_name == "ft_on_transfer")
+            .unwrap()
+            .args
+    }
+
+    #[test]
+    fn test_transfer_call_charges_fee() {
+        let (mut contract, mut context) = setup_with_fee();
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .prepaid_gas(Gas::from_tgas(300))
+            .build());
+        let _ = contract.ft_transfer_call(user2(), 10_000.into(), None, "".to_string());
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 9_900);
+        assert_eq!(
+            contract.ft_balance_of(owner()).0,
+            TOTAL_SUPPLY - 10_000 + 100
+        );
+        assert!(on_transfer_args().contains(r#""amount":"9900""#));
+    }
+
+    #[test]
+    fn test_transfer_call_with_storage_charges_fee() {
+        let (mut contract, mut context) = setup_with_fee();
+        testing_env!(context
+            .predecessor_account_id(current())
+            .attached_deposit(NearToken::from_yoctonear(0))
+            .build());
+        let _ = contract.on_receiver_storage_checked(
+            user1(),
+            user2(),
+            10_000.into(),
+            None,
+            "".to_string(),
+            NearToken::from_near(0),
+            Ok(Some(StorageBalance {
+                total: storage_deposit(),
+                available: NearToken::from_near(0),
+            })),
+        );
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 9_900);
+        assert!(on_transfer_args().contains(r#""amount":"9900""#));
+    }
+}
@@ -0,0 +1,166 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near, require, AccountId};
+
+use crate::errors::{ERR_NOTHING_TO_RECOVER, ERR_ZERO_AMOUNT};
+use crate::{emit_transfers, Contract, ContractExt, TransferLeg};
+
+#[near]
+impl Contract {
+    /// Sets the account receiving transfer fees and recovered tokens. It must be registered.
+    #[payable]
+    pub fn set_treasury(&mut self, treasury_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_valid_external_account(&treasury_id);
+        require!(
+            self.token.accounts.contains_key(&treasury_id),
+            format!("The account {} is not registered", treasury_id)
+        );
+        log!(
+            "Treasury updated from {} to {}",
+            self.treasury_id,
+            treasury_id
+        );
+        self.treasury_id = treasury_id;
+    }
+
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury_id.clone()
+    }
+
+    /// Moves tokens mistakenly sent to the contract account itself to the treasury. Tokens
+    /// escrowed for vesting schedules or held for unregistered receivers can't be recovered.
+    /// Returns the recovered amount.
+    #[payable]
+    pub fn recover_tokens(&mut self, amount: U128) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        require!(
+            amount.0 <= self.recoverable_balance(),
+            ERR_NOTHING_TO_RECOVER
+        );
+
+        let contract_id = env::current_account_id();
+        let treasury_id = self.treasury_id.clone();
+        self.internal_debit(&contract_id, amount.0);
+        self.internal_credit(&treasury_id, amount.0);
+        emit_transfers(
+            &contract_id,
+            &[TransferLeg {
+                receiver_id: treasury_id,
+                amount: amount.0,
+                memo: Some("recovered tokens".to_string()),
+            }],
+        );
+        amount
+    }
+}
+
+impl Contract {
+    /// Balance of the contract account that isn't escrowed for vesting nor held for
+    /// unregistered receivers.
+    fn recoverable_balance(&self) -> Balance {
+        self.token
+            .accounts
+            .get(&env::current_account_id())
+            .unwrap_or(0)
+            .saturating_sub(self.vesting_escrowed)
+            .saturating_sub(self.pending_claims_escrowed)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{current, owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    /// Registers user2 and makes it the treasury.
+    fn setup_treasury(contract: &mut Contract, context: &mut VMContextBuilder) {
+        register_user(contract, context, user2());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_treasury(user2());
+    }
+
+    #[test]
+    fn test_treasury_defaults_to_owner() {
+        let (contract, _) = setup();
+
+        assert_eq!(contract.get_treasury(), owner());
+    }
+
+    #[test]
+    fn test_fees_go_to_treasury() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        setup_treasury(&mut contract, &mut context);
+        contract.set_transfer_fee(100);
+
+        contract.ft_transfer(user1(), 1_000.into(), None);
+
+        assert_eq!(contract.ft_balance_of(user1()).0, 990);
+        assert_eq!(contract.ft_balance_of(user2()).0, 10);
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY - 1_000);
+    }
+
+    #[test]
+    fn test_recover_tokens_to_treasury() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, current());
+        register_user(&mut contract, &mut context, user1());
+        setup_treasury(&mut contract, &mut context);
+        contract.ft_transfer(user1(), 1_000.into(), None);
+        contract.create_vesting(user1(), 500.into(), 0.into(), 0.into(), 1_000.into());
+
+        // user1 sends tokens to the token contract by mistake
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.ft_transfer(current(), 300.into(), None);
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        assert_eq!(contract.recover_tokens(300.into()).0, 300);
+
+        assert_eq!(contract.ft_balance_of(user2()).0, 300);
+        // the vesting escrow is untouched
+        assert_eq!(contract.ft_balance_of(current()).0, 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough recoverable tokens")]
+    fn test_recover_tokens_keeps_vesting_escrow() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.create_vesting(user1(), 500.into(), 0.into(), 0.into(), 1_000.into());
+
+        contract.recover_tokens(1.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_set_treasury_requires_registration() {
+        let (mut contract, mut context) = setup();
+
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.set_treasury(user2());
+    }
+}
@@ -0,0 +1,383 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::collections::Vector;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{assert_one_yocto, env, near, require, AccountId};
+
+use crate::errors::{
+    ERR_CLIFF_BEFORE_START, ERR_NOTHING_TO_CLAIM, ERR_NO_VESTING, ERR_VESTING_DURATION,
+    ERR_ZERO_AMOUNT,
+};
+use crate::math::{checked_add_balance, checked_sub_balance};
+use crate::{Contract, ContractExt, StorageKey};
+
+/// A single linear vesting grant. An account may hold several of them at once.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingSchedule {
+    /// Total amount of tokens granted by this schedule.
+    pub total: U128,
+    /// Amount of tokens already claimed from this schedule.
+    pub claimed: U128,
+    /// Timestamp (in nanoseconds) from which tokens start to unlock.
+    pub start_ns: U64,
+    /// Timestamp (in nanoseconds) before which nothing can be claimed.
+    pub cliff_ns: U64,
+    /// Period (in nanoseconds) over which `total` unlocks linearly from `start_ns`.
+    pub duration_ns: U64,
+}
+
+impl VestingSchedule {
+    /// Returns the amount unlocked by this schedule at `timestamp`, claimed tokens included.
+    pub fn unlocked_at(&self, timestamp: u64) -> Balance {
+        let total = self.total.0;
+        if timestamp < self.cliff_ns.0 || timestamp <= self.start_ns.0 {
+            return 0;
+        }
+        let elapsed = u128::from(timestamp - self.start_ns.0);
+        let duration = u128::from(self.duration_ns.0);
+        if elapsed >= duration {
+            return total;
+        }
+        // Split the multiplication to avoid overflowing `total * elapsed`.
+        total / duration * elapsed + total % duration * elapsed / duration
+    }
+
+    /// Returns the amount that can be claimed from this schedule at `timestamp`.
+    pub fn claimable_at(&self, timestamp: u64) -> Balance {
+        self.unlocked_at(timestamp) - self.claimed.0
+    }
+}
+
+#[near]
+impl Contract {
+    /// Grants `amount` of the owner's tokens to `account_id`, unlocking linearly over
+    /// `duration_ns` from `start_ns`. Grants stack, so an account may hold several schedules.
+    /// The granted tokens are held by the contract account until claimed.
+    #[payable]
+    pub fn create_vesting(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        start_ns: U64,
+        cliff_ns: U64,
+        duration_ns: U64,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(amount.0 > 0, ERR_ZERO_AMOUNT);
+        require!(duration_ns.0 > 0, ERR_VESTING_DURATION);
+        require!(cliff_ns.0 >= start_ns.0, ERR_CLIFF_BEFORE_START);
+        require!(
+            self.token.accounts.contains_key(&account_id),
+            format!("The account {} is not registered", account_id)
+        );
+
+        let escrow_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&escrow_id) {
+            self.internal_register_account(&escrow_id);
+        }
+        self.internal_token_transfer(
+            &self.owner_id.clone(),
+            &escrow_id,
+            amount.0,
+            Some("vesting grant".to_string()),
+        );
+        self.vesting_escrowed = checked_add_balance(self.vesting_escrowed, amount.0);
+
+        let mut schedules = self.vesting.get(&account_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::VestingSchedules {
+                account_hash: env::sha256(account_id.as_bytes()),
+            })
+        });
+        schedules.push(&VestingSchedule {
+            total: amount,
+            claimed: U128(0),
+            start_ns,
+            cliff_ns,
+            duration_ns,
+        });
+        self.vesting.insert(&account_id, &schedules);
+    }
+
+    /// Claims everything unlocked so far across all of the caller's vesting schedules.
+    /// Returns the claimed amount.
+    #[payable]
+    pub fn claim_vested(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut schedules = self
+            .vesting
+            .get(&account_id)
+            .unwrap_or_else(|| env::panic_str(ERR_NO_VESTING));
+
+        let now = env::block_timestamp();
+        let mut claimed: Balance = 0;
+        for index in 0..schedules.len() {
+            let mut schedule = schedules.get(index).unwrap();
+            let claimable = schedule.claimable_at(now);
+            if claimable > 0 {
+                schedule.claimed = U128(checked_add_balance(schedule.claimed.0, claimable));
+                schedules.replace(index, &schedule);
+                claimed = checked_add_balance(claimed, claimable);
+            }
+        }
+        require!(claimed > 0, ERR_NOTHING_TO_CLAIM);
+
+        self.internal_release_escrow(&account_id, claimed, "vesting claim");
+        self.vesting_escrowed = checked_sub_balance(self.vesting_escrowed, claimed);
+        claimed.into()
+    }
+
+    /// Returns the total amount unlocked so far across all schedules of `account_id`,
+    /// including tokens that were already claimed.
+    pub fn vested_amount(&self, account_id: AccountId) -> U128 {
+        let now = env::block_timestamp();
+        self.vesting
+            .get(&account_id)
+            .map(|schedules| schedules.iter().map(|s| s.unlocked_at(now)).sum())
+            .unwrap_or(0)
+            .into()
+    }
+
+    /// Returns the amount `account_id` could claim right now.
+    pub fn claimable_amount(&self, account_id: AccountId) -> U128 {
+        let now = env::block_timestamp();
+        self.vesting
+            .get(&account_id)
+            .map(|schedules| schedules.iter().map(|s| s.claimable_at(now)).sum())
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn get_vesting_schedules(&self, account_id: AccountId) -> Vec<VestingSchedule> {
+        self.vesting
+            .get(&account_id)
+            .map(|schedules| schedules.to_vec())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1, user2, TOTAL_SUPPLY};
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn grant(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        account_id: AccountId,
+        amount: Balance,
+        start_ns: u64,
+        duration_ns: u64,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.create_vesting(
+            account_id,
+            amount.into(),
+            start_ns.into(),
+            start_ns.into(),
+            duration_ns.into(),
+        );
+    }
+
+    fn claim_at(
+        contract: &mut Contract,
+        context: &mut VMContextBuilder,
+        account_id: AccountId,
+        timestamp: u64,
+    ) -> Balance {
+        testing_env!(context
+            .predecessor_account_id(account_id)
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .block_timestamp(timestamp)
+            .build());
+        contract.claim_vested().0
+    }
+
+    #[test]
+    fn test_unlocked_at_is_linear() {
+        let schedule = VestingSchedule {
+            total: U128(1_000),
+            claimed: U128(0),
+            start_ns: U64(100),
+            cliff_ns: U64(150),
+            duration_ns: U64(1_000),
+        };
+
+        assert_eq!(schedule.unlocked_at(100), 0);
+        assert_eq!(schedule.unlocked_at(149), 0);
+        assert_eq!(schedule.unlocked_at(150), 50);
+        assert_eq!(schedule.unlocked_at(600), 500);
+        assert_eq!(schedule.unlocked_at(1_100), 1_000);
+        assert_eq!(schedule.unlocked_at(u64::MAX), 1_000);
+    }
+
+    #[test]
+    fn test_unlocked_at_does_not_overflow() {
+        // a billion tokens with 24 decimals vesting over four years
+        let total: Balance = 1_000_000_000 * 10u128.pow(24);
+        let duration_ns = 4 * 365 * 24 * 3600 * SECOND;
+        let schedule = VestingSchedule {
+            total: U128(total),
+            claimed: U128(0),
+            start_ns: U64(0),
+            cliff_ns: U64(0),
+            duration_ns: U64(duration_ns),
+        };
+
+        assert_eq!(schedule.unlocked_at(duration_ns / 2), total / 2);
+        assert_eq!(schedule.unlocked_at(duration_ns / 4), total / 4);
+    }
+
+    #[test]
+    fn test_create_vesting_moves_tokens_to_escrow() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        grant(&mut contract, &mut context, user1(), 1_000, 0, 100 * SECOND);
+
+        assert_eq!(contract.ft_balance_of(owner()).0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_balance_of(env::current_account_id()).0, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 0);
+        assert_eq!(contract.get_vesting_schedules(user1()).len(), 1);
+    }
+
+    #[test]
+    fn test_claim_two_overlapping_schedules() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        // 1000 tokens over 100s starting at 0, and 600 tokens over 60s starting at 40s
+        grant(&mut contract, &mut context, user1(), 1_000, 0, 100 * SECOND);
+        grant(
+            &mut contract,
+            &mut context,
+            user1(),
+            600,
+            40 * SECOND,
+            60 * SECOND,
+        );
+        assert_eq!(contract.get_vesting_schedules(user1()).len(), 2);
+
+        // at 70s: 700 from the first schedule and 300 from the second
+        testing_env!(context.block_timestamp(70 * SECOND).build());
+        assert_eq!(contract.vested_amount(user1()).0, 1_000);
+        assert_eq!(contract.claimable_amount(user1()).0, 1_000);
+
+        let claimed = claim_at(&mut contract, &mut context, user1(), 70 * SECOND);
+        assert_eq!(claimed, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+        assert_eq!(contract.claimable_amount(user1()).0, 0);
+
+        // at 100s both schedules are fully unlocked
+        let claimed = claim_at(&mut contract, &mut context, user1(), 100 * SECOND);
+        assert_eq!(claimed, 600);
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_600);
+        assert_eq!(contract.ft_balance_of(env::current_account_id()).0, 0);
+        assert_eq!(contract.vested_amount(user1()).0, 1_600);
+
+        let schedules = contract.get_vesting_schedules(user1());
+        assert_eq!(schedules[0].claimed.0, 1_000);
+        assert_eq!(schedules[1].claimed.0, 600);
+    }
+
+    #[test]
+    fn test_schedules_are_per_account() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        register_user(&mut contract, &mut context, user2());
+
+        grant(&mut contract, &mut context, user1(), 1_000, 0, 100 * SECOND);
+        grant(&mut contract, &mut context, user2(), 500, 0, 100 * SECOND);
+
+        let claimed = claim_at(&mut contract, &mut context, user2(), 100 * SECOND);
+        assert_eq!(claimed, 500);
+        assert_eq!(contract.claimable_amount(user1()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to claim")]
+    fn test_claim_before_cliff_panics() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        grant(
+            &mut contract,
+            &mut context,
+            user1(),
+            1_000,
+            10 * SECOND,
+            100 * SECOND,
+        );
+
+        claim_at(&mut contract, &mut context, user1(), 5 * SECOND);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner's method")]
+    fn test_create_vesting_only_owner() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.create_vesting(user1(), 1_000.into(), 0.into(), 0.into(), 100.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't unregister the account with unclaimed vesting")]
+    fn test_unregister_with_unclaimed_vesting() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        grant(&mut contract, &mut context, user1(), 1_000, 0, 100 * SECOND);
+
+        testing_env!(context
+            .predecessor_account_id(user1())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+        contract.storage_unregister(None);
+    }
+
+    fn as_owner(context: &mut VMContextBuilder) {
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .build());
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract paused")]
+    fn test_claim_while_paused_panics() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        grant(&mut contract, &mut context, user1(), 1_000, 0, 100 * SECOND);
+        as_owner(&mut context);
+        contract.set_paused(true, None);
+
+        claim_at(&mut contract, &mut context, user1(), 100 * SECOND);
+    }
+
+    #[test]
+    #[should_panic(expected = "Exceeds max holding")]
+    fn test_claim_above_max_holding_panics() {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        grant(&mut contract, &mut context, user1(), 1_000, 0, 100 * SECOND);
+        as_owner(&mut context);
+        contract.set_max_balance_per_account(Some(500.into()));
+
+        claim_at(&mut contract, &mut context, user1(), 100 * SECOND);
+    }
+}
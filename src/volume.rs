@@ -0,0 +1,120 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, log, near, require};
+
+use crate::errors::ERR_VOLUME_CAP_EXCEEDED;
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    /// Caps the total amount transferred through the contract during a single epoch, e.g. for
+    /// a controlled launch, or removes the cap with `None`. The volume is counted even
+    /// without a cap, so setting one mid-epoch takes the transfers already made into account.
+    #[payable]
+    pub fn set_volume_cap_per_epoch(&mut self, cap: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        log!("Volume cap per epoch set to {:?}", cap);
+        self.volume_cap_per_epoch = cap.map(|cap| cap.0);
+    }
+
+    pub fn get_volume_cap_per_epoch(&self) -> Option<U128> {
+        self.volume_cap_per_epoch.map(U128)
+    }
+
+    /// Returns the amount transferred so far during the current epoch.
+    pub fn get_epoch_volume(&self) -> U128 {
+        self.current_epoch_volume().into()
+    }
+}
+
+impl Contract {
+    fn current_epoch_volume(&self) -> Balance {
+        if self.volume_epoch_height == env::epoch_height() {
+            self.epoch_volume
+        } else {
+            0
+        }
+    }
+
+    /// Whether transferring `amount` more would exceed the volume cap of the current epoch.
+    pub(crate) fn exceeds_volume_cap(&self, amount: Balance) -> bool {
+        self.volume_cap_per_epoch.is_some_and(|cap| {
+            self.current_epoch_volume()
+                .checked_add(amount)
+                .is_none_or(|volume| volume > cap)
+        })
+    }
+
+    /// Adds `amount` to the volume of the current epoch, starting from zero after a
+    /// rollover. Panics if it would exceed the cap.
+    pub(crate) fn record_volume(&mut self, amount: Balance) {
+        require!(!self.exceeds_volume_cap(amount), ERR_VOLUME_CAP_EXCEEDED);
+        self.epoch_volume = self.current_epoch_volume().saturating_add(amount);
+        self.volume_epoch_height = env::epoch_height();
+    }
+
+    /// Takes `amount` refunded by a transfer call receiver back out of the volume of the
+    /// current epoch. Refunds resolved after a rollover leave the new epoch untouched.
+    pub(crate) fn release_volume(&mut self, amount: Balance) {
+        if self.volume_epoch_height == env::epoch_height() {
+            self.epoch_volume = self.epoch_volume.saturating_sub(amount);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_contract_standards::fungible_token::FungibleTokenCore;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, NearToken};
+
+    use super::*;
+    use crate::tests::{owner, register_user, setup, user1};
+
+    /// Caps the volume at 1_000 per epoch, starting in epoch 1.
+    fn setup_with_cap() -> (Contract, VMContextBuilder) {
+        let (mut contract, mut context) = setup();
+        register_user(&mut contract, &mut context, user1());
+        testing_env!(context
+            .predecessor_account_id(owner())
+            .attached_deposit(NearToken::from_yoctonear(1))
+            .epoch_height(1)
+            .build());
+        contract.set_volume_cap_per_epoch(Some(1_000.into()));
+        (contract, context)
+    }
+
+    #[test]
+    fn test_transfers_under_volume_cap() {
+        let (mut contract, _) = setup_with_cap();
+
+        contract.ft_transfer(user1(), 400.into(), None);
+        contract.ft_transfer(user1(), 600.into(), None);
+
+        assert_eq!(contract.get_epoch_volume().0, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer volume cap of this epoch exceeded")]
+    fn test_transfer_above_volume_cap() {
+        let (mut contract, _) = setup_with_cap();
+
+        contract.ft_transfer(user1(), 600.into(), None);
+        contract.ft_transfer(user1(), 401.into(), None);
+    }
+
+    #[test]
+    fn test_volume_resets_after_epoch() {
+        let (mut contract, mut context) = setup_with_cap();
+        contract.ft_transfer(user1(), 1_000.into(), None);
+
+        testing_env!(context.epoch_height(2).build());
+        assert_eq!(contract.get_epoch_volume().0, 0);
+        contract.ft_transfer(user1(), 1_000.into(), None);
+
+        assert_eq!(contract.get_epoch_volume().0, 1_000);
+        assert_eq!(contract.ft_balance_of(user1()).0, 2_000);
+    }
+}
@@ -2,7 +2,10 @@ use std::sync::LazyLock;
 
 use cargo_near_build::BuildOpts;
 use near_contract_standards::fungible_token::metadata::{FungibleTokenMetadata, FT_METADATA_SPEC};
-use near_sdk::{json_types::U128, AccountId, NearToken};
+use near_sdk::{
+    json_types::{U128, U64},
+    AccountId, NearToken,
+};
 use near_workspaces::{Account, Contract, DevNetwork, Worker};
 
 const INITIAL_BALANCE: NearToken = NearToken::from_near(30);
@@ -16,24 +19,19 @@ static FUNGIBLE_TOKEN_CONTRACT_WASM: LazyLock<Vec<u8>> = LazyLock::new(|| {
     })
     .expect("Could not compile Fungible Token contract for tests");
 
-    let contract_wasm = std::fs::read(&artifact.path).expect(
-        format!(
+    std::fs::read(&artifact.path).unwrap_or_else(|_| {
+        panic!(
             "Could not read Fungible Token WASM file from {}",
             artifact.path
         )
-        .as_str(),
-    );
-
-    contract_wasm
+    })
 });
 
 static DEFI_CONTRACT_WASM: LazyLock<Vec<u8>> = LazyLock::new(|| {
     let artifact_path = "tests/contracts/defi/res/defi.wasm";
 
-    let contract_wasm = std::fs::read(artifact_path)
-        .expect(format!("Could not read DeFi WASM file from {}", artifact_path).as_str());
-
-    contract_wasm
+    std::fs::read(artifact_path)
+        .unwrap_or_else(|_| panic!("Could not read DeFi WASM file from {}", artifact_path))
 });
 
 pub async fn init_accounts(root: &Account) -> anyhow::Result<(Account, Account, Account, Account)> {
@@ -63,7 +61,7 @@ pub async fn init_accounts(root: &Account) -> anyhow::Result<(Account, Account,
         .await?
         .into_result()?;
 
-    return Ok((alice, bob, charlie, dave));
+    Ok((alice, bob, charlie, dave))
 }
 
 pub async fn init_contracts(
@@ -87,6 +85,8 @@ pub async fn init_contracts(
                 reference_hash: None,
                 decimals: 24,
             },
+            None::<U64>,
+            None::<AccountId>,
         ))
         .max_gas()
         .transact()
@@ -112,7 +112,7 @@ pub async fn init_contracts(
         .await?;
     assert!(res.is_success());
 
-    return Ok((ft_contract, defi_contract));
+    Ok((ft_contract, defi_contract))
 }
 
 pub async fn register_user(contract: &Contract, account_id: &AccountId) -> anyhow::Result<()> {